@@ -0,0 +1,235 @@
+//! # opg-macros
+//!
+//! `opg-macros` provides `opg_grammar!`, which
+//! generates the operator precedence parser of
+//! a grammar at compile time.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use opg::{build, gen_table, table, Production, EPSILON};
+
+///
+/// A symbol in the grammar with its place in the source.
+///
+struct Symbol {
+    text: String,
+    span: Span,
+}
+
+///
+/// Flatten the tokens into the symbols.
+///
+/// ## Principles
+/// - A group is split into its delimiters and the inside.
+/// - The joint punctuations like `->` are one symbol.
+/// - A string literal is a terminal without the quotes,
+///   for the terminals which are not Rust tokens.
+///
+fn flatten(stream: TokenStream, symbols: &mut Vec<Symbol>) {
+    let mut punct: Option<Symbol> = None;
+    for tree in stream {
+        let joint = match &tree {
+            TokenTree::Punct(p) => {
+                let s = punct.get_or_insert(Symbol {
+                    text: String::new(),
+                    span: p.span(),
+                });
+                s.text.push(p.as_char());
+                p.spacing() == Spacing::Joint
+            }
+            _ => false,
+        };
+        if joint {
+            continue;
+        }
+        if let Some(s) = punct.take() {
+            symbols.push(s);
+        }
+        match tree {
+            TokenTree::Group(g) => {
+                let (open, close) = match g.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+                if !open.is_empty() {
+                    symbols.push(Symbol {
+                        text: open.to_string(),
+                        span: g.span_open(),
+                    });
+                }
+                flatten(g.stream(), symbols);
+                if !close.is_empty() {
+                    symbols.push(Symbol {
+                        text: close.to_string(),
+                        span: g.span_close(),
+                    });
+                }
+            }
+            TokenTree::Ident(i) => symbols.push(Symbol {
+                text: i.to_string(),
+                span: i.span(),
+            }),
+            TokenTree::Literal(l) => {
+                let text = l.to_string();
+                let text = match text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+                    Some(t) => t.replace("\\\"", "\"").replace("\\\\", "\\"),
+                    None => text,
+                };
+                symbols.push(Symbol { text, span: l.span() })
+            }
+            TokenTree::Punct(_) => {}
+        }
+    }
+    if let Some(s) = punct.take() {
+        symbols.push(s);
+    }
+}
+
+///
+/// The productions with the spans of the right sides.
+///
+type Rules = (Vec<Production>, Vec<Vec<Span>>);
+
+///
+/// Parse the symbols into the productions,
+/// keeping the spans of the right sides.
+///
+/// ## Principles
+/// A rule starts at a symbol followed by `->`, and the
+/// alternatives are split by `|`. A rule could also be
+/// ended with `;`. A single `ε` is the empty right side.
+///
+fn parse_rules(symbols: &[Symbol]) -> Result<Rules, (String, Span)> {
+    let mut productions: Vec<Production> = Vec::new();
+    let mut spans: Vec<Vec<Span>> = Vec::new();
+    let mut i = 0;
+    while i < symbols.len() {
+        if symbols[i].text == ";" {
+            i += 1;
+            continue;
+        }
+        let left = &symbols[i];
+        match symbols.get(i + 1) {
+            Some(arrow) if arrow.text == "->" => {}
+            _ => return Err((format!("expected `->` after `{}`", left.text), left.span)),
+        }
+        i += 2;
+        let mut alternative: Vec<&Symbol> = Vec::new();
+        loop {
+            let end = i >= symbols.len()
+                || symbols[i].text == ";"
+                || symbols.get(i + 1).is_some_and(|s| s.text == "->");
+            if end || symbols[i].text == "|" {
+                if alternative.len() == 1 && alternative[0].text == EPSILON {
+                    alternative.clear();
+                }
+                let right = alternative.iter().map(|s| s.text.clone()).collect();
+                productions.push(Production::new(&left.text, right));
+                spans.push(alternative.iter().map(|s| s.span).collect());
+                alternative.clear();
+                if end {
+                    break;
+                }
+            } else {
+                alternative.push(&symbols[i]);
+            }
+            i += 1;
+        }
+    }
+    Ok((productions, spans))
+}
+
+///
+/// Find the place of a conflict between `a` and `b`,
+/// where `a` is followed by `b` in a right side if possible.
+///
+fn conflict_span(productions: &[Production], spans: &[Vec<Span>], a: &str, b: &str) -> Span {
+    let mut first: Option<Span> = None;
+    for (p, ss) in productions.iter().zip(spans.iter()) {
+        for (k, v) in p.right.iter().enumerate() {
+            if v == a {
+                if p.right[k + 1..].iter().any(|w| w == b) {
+                    return ss[k];
+                }
+                first.get_or_insert(ss[k]);
+            }
+        }
+    }
+    first.unwrap_or_else(Span::call_site)
+}
+
+///
+/// Emit `compile_error!` at the span.
+///
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut literal = Literal::string(message);
+    literal.set_span(span);
+    let mut group = Group::new(Delimiter::Parenthesis, TokenStream::from(TokenTree::Literal(literal)));
+    group.set_span(span);
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    let mut semi = Punct::new(';', Spacing::Alone);
+    semi.set_span(span);
+    let trees: Vec<TokenTree> = vec![
+        Ident::new("compile_error", span).into(),
+        bang.into(),
+        group.into(),
+        semi.into(),
+    ];
+    trees.into_iter().collect()
+}
+
+///
+/// Generate the operator precedence parser of the grammar.
+///
+/// ## Example
+/// ```
+/// mod expr {
+///     opg_macros::opg_grammar! {
+///         E -> E + T | T
+///         T -> T * F | F
+///         F -> ( E ) | i
+///     }
+/// }
+///
+/// let value = expr::parse(&[expr::Token::I], &mut reducer);
+/// ```
+///
+/// ## Principles
+/// The items are the ones of `opg codegen --lang rust`,
+/// see `opg::codegen::rust()`. If the grammar is ambiguous,
+/// the compilation fails at the terminal of every
+/// precedence conflict.
+///
+#[proc_macro]
+pub fn opg_grammar(input: TokenStream) -> TokenStream {
+    let mut symbols: Vec<Symbol> = Vec::new();
+    flatten(input, &mut symbols);
+    let (productions, spans) = match parse_rules(&symbols) {
+        Ok(rules) => rules,
+        Err((message, span)) => return compile_error(&message, span),
+    };
+    if productions.is_empty() {
+        return compile_error("the grammar is empty", Span::call_site());
+    }
+    let table = gen_table(&productions);
+    if !table.conflicts().is_empty() {
+        let mut errors = TokenStream::new();
+        for conflict in table.conflicts() {
+            let (a, b) = (&conflict.0, &conflict.1);
+            let span = if a == "$" {
+                conflict_span(&productions, &spans, b, "")
+            } else {
+                conflict_span(&productions, &spans, a, b)
+            };
+            errors.extend(compile_error(&table::format_conflict(conflict), span));
+        }
+        return errors;
+    }
+    match build::generate_code(&productions) {
+        Ok(code) => code.parse().unwrap(),
+        Err(message) => compile_error(&message, Span::call_site()),
+    }
+}
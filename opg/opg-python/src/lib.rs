@@ -0,0 +1,303 @@
+//! # opg-python
+//!
+//! `opg-python` provides the Python module `opg`, which
+//! exposes the grammar, the FIRSTVT and LASTVT sets, the
+//! operator precedence table and the parser, and returns
+//! the native dicts and lists of Python.
+
+use std::collections::{HashMap, HashSet};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use pyo3::IntoPyObjectExt;
+
+use opg::lexer::{self, Token};
+use opg::parser::{self, Reducer};
+use opg::semantic::{self, Value};
+use opg::table::OpTable;
+use opg::{check_grammar, format_grammar, gen_firstvt, gen_lastvt, gen_productions, gen_table};
+use opg::{get_non_terminals, ordered_symbols, Production, EPSILON};
+
+///
+/// A node of the parse tree.
+///
+enum Node {
+    Leaf(Token),
+    /// the index of the production and the children.
+    Inner(usize, Vec<Node>),
+}
+
+///
+/// A `Reducer` which builds the parse tree.
+///
+struct TreeBuilder<'a> {
+    productions: &'a [Production],
+}
+
+impl Reducer for TreeBuilder<'_> {
+    type Value = Node;
+
+    fn shift(&mut self, token: &Token) -> Result<Node, String> {
+        Ok(Node::Leaf(token.clone()))
+    }
+
+    fn reduce(&mut self, production: &Production, children: Vec<Node>) -> Result<Node, String> {
+        let k = self.productions.iter().position(|p| std::ptr::eq(p, production)).unwrap();
+        Ok(Node::Inner(k, children))
+    }
+}
+
+fn value_error(e: String) -> PyErr {
+    PyValueError::new_err(e)
+}
+
+///
+/// An operator precedence grammar with its table.
+///
+/// ## Example
+/// ```python
+/// import opg
+///
+/// g = opg.Grammar("E -> E + T | T\nT -> T * F | F\nF -> ( E ) | i")
+/// g.firstvt()["E"]       # ['+', '*', '(', 'i']
+/// g.table()["relations"]["+"]["*"]       # '<'
+/// g.evaluate("1 + 2 * 3")        # 7
+/// ```
+///
+#[pyclass(module = "opg")]
+struct Grammar {
+    productions: Vec<Production>,
+    nts: HashSet<String>,
+    /// the non-terminals in the order of appearance.
+    nv: Vec<String>,
+    /// the terminals in the order of appearance, without `$`.
+    tv: Vec<String>,
+    table: OpTable,
+}
+
+impl Grammar {
+    ///
+    /// Arrange the sets in the order of the non-terminals,
+    /// with the terminals in the order of appearance.
+    ///
+    fn sets<'py>(&self, py: Python<'py>, sets: &HashMap<String, HashSet<String>>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for v in self.nv.iter() {
+            let set: Vec<&String> = match sets.get(v) {
+                Some(set) => self.tv.iter().filter(|t| set.contains(*t)).collect(),
+                None => Vec::new(),
+            };
+            dict.set_item(v, set)?;
+        }
+        Ok(dict)
+    }
+
+    ///
+    /// Parse the sentence with the table.
+    ///
+    fn run<R: Reducer>(&self, sentence: &str, reducer: &mut R) -> PyResult<R::Value> {
+        if !self.table.conflicts().is_empty() {
+            return Err(value_error("the grammar is ambiguous".to_string()));
+        }
+        lexer::tokenize(sentence, self.table.terminals())
+            .and_then(|tokens| parser::parse(&self.table, &self.productions, &self.nts, &tokens, reducer))
+            .map_err(value_error)
+    }
+
+    fn tree<'py>(&self, py: Python<'py>, node: Node) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        match node {
+            Node::Leaf(token) => {
+                dict.set_item("symbol", token.kind)?;
+                dict.set_item("text", token.text)?;
+            }
+            Node::Inner(k, children) => {
+                dict.set_item("symbol", &self.productions[k].left)?;
+                dict.set_item("production", k)?;
+                let children = children
+                    .into_iter()
+                    .map(|child| self.tree(py, child))
+                    .collect::<PyResult<Vec<_>>>()?;
+                dict.set_item("children", children)?;
+            }
+        }
+        Ok(dict)
+    }
+}
+
+#[pymethods]
+impl Grammar {
+    ///
+    /// Read the grammar in the syntax of the grammar file,
+    /// and build its operator precedence table.
+    ///
+    #[new]
+    fn new(text: &str) -> PyResult<Self> {
+        check_grammar(text).map_err(value_error)?;
+        let productions = gen_productions(text);
+        if productions.is_empty() {
+            return Err(value_error("the grammar is empty".to_string()));
+        }
+        let nts = get_non_terminals(&productions);
+        let (nv, tv) = ordered_symbols(&productions, &nts);
+        let table = gen_table(&productions);
+        Ok(Grammar {
+            productions,
+            nts,
+            nv,
+            tv,
+            table,
+        })
+    }
+
+    ///
+    /// The productions as the dicts of `left`, `right`,
+    /// `action` and `templates`, where `ε` is `[]`.
+    ///
+    #[getter]
+    fn productions<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        let list = PyList::empty(py);
+        for p in self.productions.iter() {
+            let dict = PyDict::new(py);
+            dict.set_item("left", &p.left)?;
+            dict.set_item("right", &p.right)?;
+            dict.set_item("action", &p.action)?;
+            dict.set_item("templates", &p.templates)?;
+            list.append(dict)?;
+        }
+        Ok(list)
+    }
+
+    ///
+    /// The non-terminals in the order of appearance.
+    ///
+    #[getter]
+    fn non_terminals(&self) -> Vec<String> {
+        self.nv.clone()
+    }
+
+    ///
+    /// The terminals in the order of appearance, without `$`.
+    ///
+    #[getter]
+    fn terminals(&self) -> Vec<String> {
+        self.tv.clone()
+    }
+
+    ///
+    /// The FIRSTVT sets as a dict from
+    /// the non-terminals to the lists of terminals.
+    ///
+    fn firstvt<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        self.sets(py, &gen_firstvt(&self.productions, &self.nts))
+    }
+
+    ///
+    /// The LASTVT sets as a dict from
+    /// the non-terminals to the lists of terminals.
+    ///
+    fn lastvt<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        self.sets(py, &gen_lastvt(&self.productions, &self.nts))
+    }
+
+    ///
+    /// The relation between terminal `a` on the left
+    /// and terminal `b` on the right, one of `<`, `=`,
+    /// `>` or `None` if the pair has no relation.
+    ///
+    fn relation(&self, a: &str, b: &str) -> Option<char> {
+        self.table.get(a, b)
+    }
+
+    ///
+    /// The operator precedence table as a dict of
+    /// - `terminals`: the terminals in order, ended with `$`.
+    /// - `relations`: `relations[a][b]` is the relation of
+    ///   `a` on the left and `b` on the right, if there is one.
+    /// - `conflicts`: the tuples of `(a, b, kept, rejected)`.
+    /// - `functions`: the dicts `f` and `g` of the precedence
+    ///   functions, or `None` if they do not exist.
+    ///
+    fn table<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let mut tv = self.tv.clone();
+        tv.push("$".to_string());
+        let relations = PyDict::new(py);
+        for a in tv.iter() {
+            let row = PyDict::new(py);
+            for b in tv.iter() {
+                if let Some(c) = self.table.get(a, b) {
+                    row.set_item(b, c)?;
+                }
+            }
+            relations.set_item(a, row)?;
+        }
+        let functions = match self.table.gen_functions(&tv) {
+            Some((f, g)) => {
+                let functions = PyDict::new(py);
+                for (name, values) in [("f", f), ("g", g)] {
+                    let dict = PyDict::new(py);
+                    for (t, x) in tv.iter().zip(values) {
+                        dict.set_item(t, x)?;
+                    }
+                    functions.set_item(name, dict)?;
+                }
+                functions.into_bound_py_any(py)?
+            }
+            None => py.None().into_bound(py),
+        };
+        let dict = PyDict::new(py);
+        dict.set_item("terminals", &tv)?;
+        dict.set_item("relations", relations)?;
+        dict.set_item("conflicts", self.table.conflicts().to_vec())?;
+        dict.set_item("functions", functions)?;
+        Ok(dict)
+    }
+
+    ///
+    /// Parse the sentence into the tree, where a leaf is the
+    /// dict of `symbol` and `text`, and a reduction is the dict
+    /// of `symbol`, the index of the `production` and `children`.
+    ///
+    /// Raise `ValueError` if the grammar is ambiguous,
+    /// or the sentence is not in the language.
+    ///
+    fn parse<'py>(&self, py: Python<'py>, sentence: &str) -> PyResult<Bound<'py, PyDict>> {
+        let mut builder = TreeBuilder {
+            productions: &self.productions,
+        };
+        let node = self.run(sentence, &mut builder)?;
+        self.tree(py, node)
+    }
+
+    ///
+    /// Evaluate the sentence by the semantic actions into
+    /// an `int`, a `float` or a `str`, see `semantic::Evaluator`.
+    ///
+    fn evaluate<'py>(&self, py: Python<'py>, sentence: &str) -> PyResult<Bound<'py, PyAny>> {
+        match self.run(sentence, &mut semantic::Evaluator)? {
+            Value::Int(i) => i.into_bound_py_any(py),
+            Value::Float(f) => f.into_bound_py_any(py),
+            Value::Text(t) => t.into_bound_py_any(py),
+        }
+    }
+
+    fn __str__(&self) -> String {
+        format_grammar(&self.productions)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Grammar({:?})", format_grammar(&self.productions).trim_end())
+    }
+}
+
+///
+/// The Python module `opg`.
+///
+#[pymodule]
+#[pyo3(name = "opg")]
+fn opg_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("EPSILON", EPSILON)?;
+    m.add_class::<Grammar>()?;
+    Ok(())
+}
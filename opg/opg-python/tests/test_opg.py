@@ -0,0 +1,169 @@
+"""Tests of the Python module `opg`.
+
+Build the module and run the tests in the directory `opg`:
+
+    cargo build -p opg-python
+    python3 -m unittest discover -s opg-python/tests
+
+The module is loaded from the library built by cargo, or from
+the path in the environment variable `OPG_PYTHON_LIB`, or else
+imported as an installed package.
+"""
+
+import importlib.machinery
+import importlib.util
+import os
+import sys
+import unittest
+
+ROOT = os.path.dirname(os.path.dirname(os.path.dirname(os.path.abspath(__file__))))
+
+EXPR = """E -> E + T | T
+T -> T * F | F
+F -> ( E ) | i
+"""
+
+
+def load():
+    """Load the module `opg` from the built library."""
+    if sys.platform == "win32":
+        name = "opg_python.dll"
+    elif sys.platform == "darwin":
+        name = "libopg_python.dylib"
+    else:
+        name = "libopg_python.so"
+    path = os.environ.get("OPG_PYTHON_LIB")
+    if path is None:
+        for profile in ("debug", "release"):
+            candidate = os.path.join(ROOT, "target", profile, name)
+            if os.path.exists(candidate):
+                path = candidate
+                break
+    if path is None:
+        import opg
+
+        return opg
+    loader = importlib.machinery.ExtensionFileLoader("opg", path)
+    spec = importlib.util.spec_from_loader("opg", loader)
+    module = importlib.util.module_from_spec(spec)
+    loader.exec_module(module)
+    return module
+
+
+opg = load()
+
+
+class GrammarTest(unittest.TestCase):
+    def setUp(self):
+        self.grammar = opg.Grammar(EXPR)
+
+    def test_productions(self):
+        productions = self.grammar.productions
+        self.assertEqual(len(productions), 6)
+        self.assertEqual(
+            productions[0],
+            {"left": "E", "right": ["E", "+", "T"], "action": None, "templates": []},
+        )
+        self.assertEqual(productions[5]["right"], ["i"])
+
+    def test_symbols(self):
+        self.assertEqual(self.grammar.non_terminals, ["E", "T", "F"])
+        self.assertEqual(self.grammar.terminals, ["+", "*", "(", ")", "i"])
+
+    def test_epsilon(self):
+        grammar = opg.Grammar("S -> a S | " + opg.EPSILON)
+        self.assertEqual(grammar.productions[1]["right"], [])
+
+    def test_str(self):
+        self.assertEqual(str(opg.Grammar(str(self.grammar))), str(self.grammar))
+        self.assertTrue(repr(self.grammar).startswith("Grammar("))
+
+    def test_invalid(self):
+        for text in ["", "E E", "-> a", "E F -> a"]:
+            with self.assertRaises(ValueError):
+                opg.Grammar(text)
+
+
+class SetsTest(unittest.TestCase):
+    def setUp(self):
+        self.grammar = opg.Grammar(EXPR)
+
+    def test_firstvt(self):
+        self.assertEqual(
+            self.grammar.firstvt(),
+            {"E": ["+", "*", "(", "i"], "T": ["*", "(", "i"], "F": ["(", "i"]},
+        )
+
+    def test_lastvt(self):
+        self.assertEqual(
+            self.grammar.lastvt(),
+            {"E": ["+", "*", ")", "i"], "T": ["*", ")", "i"], "F": [")", "i"]},
+        )
+
+
+class TableTest(unittest.TestCase):
+    def setUp(self):
+        self.table = opg.Grammar(EXPR).table()
+
+    def test_terminals(self):
+        self.assertEqual(self.table["terminals"], ["+", "*", "(", ")", "i", "$"])
+
+    def test_relations(self):
+        relations = self.table["relations"]
+        self.assertEqual(relations["+"]["*"], "<")
+        self.assertEqual(relations["*"]["+"], ">")
+        self.assertEqual(relations["+"]["+"], ">")
+        self.assertEqual(relations["("][")"], "=")
+        self.assertEqual(relations["$"]["i"], "<")
+        self.assertNotIn("(", relations[")"])
+        self.assertEqual(opg.Grammar(EXPR).relation(")", "("), None)
+
+    def test_functions(self):
+        functions = self.table["functions"]
+        relations = self.table["relations"]
+        f, g = functions["f"], functions["g"]
+        self.assertEqual(list(f), self.table["terminals"])
+        for a, row in relations.items():
+            for b, c in row.items():
+                expected = "<" if f[a] < g[b] else "=" if f[a] == g[b] else ">"
+                self.assertEqual(c, expected, (a, b))
+
+    def test_conflicts(self):
+        self.assertEqual(self.table["conflicts"], [])
+        table = opg.Grammar("E -> E + E | i").table()
+        self.assertEqual(table["conflicts"], [("+", "+", "<", ">")])
+
+
+class ParserTest(unittest.TestCase):
+    def setUp(self):
+        self.grammar = opg.Grammar(EXPR)
+
+    def test_parse(self):
+        tree = self.grammar.parse("a + b")
+        self.assertEqual(tree["symbol"], "E")
+        self.assertEqual(tree["production"], 0)
+        left, plus, right = tree["children"]
+        self.assertEqual(plus, {"symbol": "+", "text": "+"})
+        self.assertEqual(left["children"], [{"symbol": "i", "text": "a"}])
+        self.assertEqual(right["children"], [{"symbol": "i", "text": "b"}])
+
+    def test_evaluate(self):
+        self.assertEqual(self.grammar.evaluate("1 + 2 * 3"), 7)
+        self.assertEqual(self.grammar.evaluate("( 1 + 2 ) * 3"), 9)
+        self.assertEqual(self.grammar.evaluate("1.5 * 2"), 3.0)
+        self.assertIsInstance(self.grammar.evaluate("1.5 * 2"), float)
+
+    def test_actions(self):
+        grammar = opg.Grammar("E -> E - T { $1 - $3 } | T\nT -> i")
+        self.assertEqual(grammar.evaluate("10 - 3 - 2"), 5)
+
+    def test_errors(self):
+        for sentence in ["1 +", "( 1", "1 2"]:
+            with self.assertRaises(ValueError):
+                self.grammar.evaluate(sentence)
+        with self.assertRaises(ValueError):
+            opg.Grammar("E -> E + E | i").parse("i + i")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,206 @@
+//! # antlr
+//!
+//! `antlr` exports the grammar as an ANTLR4 `.g4`
+//! file, and imports the parser rules of a simple
+//! `.g4` file.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::grammar::GrammarBuilder;
+use crate::interop::{self, Tok};
+use crate::{get_non_terminals, ordered_symbols, Production};
+
+/// The keywords which could not be the rule names.
+const KEYWORDS: [&str; 15] = [
+    "catch", "channels", "finally", "fragment", "grammar", "import", "lexer", "locals", "mode", "options",
+    "parser", "returns", "throws", "tokens", "EOF",
+];
+
+///
+/// Name a non-terminal as a parser rule,
+/// which starts with a lowercase letter.
+///
+fn rule_name(v: &str) -> String {
+    let mut name = interop::identifier(v);
+    name = name[..1].to_ascii_lowercase() + &name[1..];
+    if name.starts_with('_') {
+        name.insert(0, 'r');
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+///
+/// Spell a terminal as a literal in ANTLR.
+///
+fn literal(t: &str) -> String {
+    format!("'{}'", t.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+///
+/// Export the grammar as an ANTLR4 `.g4` file.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `name` the name of the grammar, which should
+/// be the same as the name of the file.
+///
+/// ## Return
+/// The combined grammar, where the semantic
+/// actions of `opg` are left out.
+///
+/// ## Example
+/// ```
+/// fs::write("Expr.g4", antlr::export(&productions, "Expr"))?;
+/// ```
+///
+/// ## Principles
+/// The non-terminals are named as the parser rules starting
+/// with a lowercase letter, and the starting one comes first.
+/// The terminals are written as literals, which ANTLR turns
+/// into the implicit tokens, and the whitespace is skipped.
+/// ANTLR4 only supports the direct left recursion, so the
+/// indirect one should be eliminated first by
+/// `transform::eliminate_left_recursion()`.
+///
+pub fn export(productions: &[Production], name: &str) -> String {
+    let nts = get_non_terminals(productions);
+    let (nv, tv) = ordered_symbols(productions, &nts);
+    // a rule named as a terminal could not be imported back
+    let mut used: HashSet<String> = tv.iter().cloned().collect();
+    let mut names: HashMap<String, String> = HashMap::new();
+    for v in nv.iter() {
+        names.insert(v.clone(), interop::unique(rule_name(v), &mut used));
+    }
+    let spell = |v: &String| names.get(v).cloned().unwrap_or_else(|| literal(v));
+    let mut output = format!("// Generated by opg\n\ngrammar {};\n", name);
+    for v in nv.iter() {
+        output += &format!("\n{}\n", names[v]);
+        for (i, p) in productions.iter().filter(|p| p.left == *v).enumerate() {
+            let right: Vec<String> = p.right.iter().map(spell).collect();
+            let right = if right.is_empty() { "/* ε */".to_string() } else { right.join(" ") };
+            output += &format!("    {} {}\n", if i == 0 { ":" } else { "|" }, right);
+        }
+        output += "    ;\n";
+    }
+    output += "\nWS\n    : [ \\t\\r\\n]+ -> skip\n    ;\n";
+    output
+}
+
+///
+/// Skip the tokens from `i` to the next `;`.
+///
+fn skip_statement(tokens: &[(Tok, usize)], mut i: usize) -> usize {
+    while i < tokens.len() && tokens[i].0 != Tok::Punct(';') {
+        i += 1;
+    }
+    i + 1
+}
+
+///
+/// Import the parser rules of an ANTLR4 `.g4` file.
+///
+/// ## Return
+/// The productions, or the error with the line number.
+///
+/// ## Principles
+/// 1. Skip the header, the options, the named actions
+///    and the lexer rules, which start with an uppercase letter.
+/// 2. A parser rule is `left : right | right ... ;`, where the
+///    literals and the token names are the terminals. The
+///    labels, the actions and `EOF` are skipped.
+/// 3. The operators of EBNF like `( )`, `?`, `*` and `+`
+///    are not supported, which should be expanded into rules.
+/// 4. The productions are built by `GrammarBuilder`, and
+///    the first rule is the starting one.
+///
+pub fn import(text: &str) -> Result<Vec<Production>, String> {
+    let tokens = interop::scan(text)?;
+    let ident = |i: usize| match tokens.get(i) {
+        Some((Tok::Ident(s), _)) => Some(s.as_str()),
+        _ => None,
+    };
+    let mut builder = GrammarBuilder::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let (tok, line) = &tokens[i];
+        let left = match tok {
+            Tok::Ident(s) if s == "lexer" && ident(i + 1) == Some("grammar") => {
+                return Err(format!("line {}: a lexer grammar has no parser rules", line));
+            }
+            Tok::Ident(s) if ["grammar", "parser", "import", "fragment", "mode"].contains(&s.as_str()) => {
+                i = skip_statement(&tokens, i);
+                continue;
+            }
+            Tok::Ident(s) if ["options", "tokens", "channels"].contains(&s.as_str()) => {
+                i += 2;
+                continue;
+            }
+            // a named action like `@header { ... }`
+            Tok::Punct('@') => {
+                while i < tokens.len() && tokens[i].0 != Tok::Block {
+                    i += 1;
+                }
+                i += 1;
+                continue;
+            }
+            Tok::Ident(s) if s.starts_with(char::is_uppercase) => {
+                i = skip_statement(&tokens, i);
+                continue;
+            }
+            Tok::Ident(s) => s.clone(),
+            tok => return Err(format!("line {}: expected a rule, found {}", line, tok)),
+        };
+        // skip the arguments, `returns [...]` and so on
+        while i < tokens.len() && tokens[i].0 != Tok::Punct(':') {
+            i += 1;
+        }
+        i += 1;
+        let mut alternatives: Vec<Vec<String>> = vec![Vec::new()];
+        while i < tokens.len() {
+            let (tok, line) = &tokens[i];
+            match tok {
+                Tok::Punct(';') => {
+                    i += 1;
+                    break;
+                }
+                Tok::Punct('|') => alternatives.push(Vec::new()),
+                // a label of the alternative like `# Add`
+                Tok::Punct('#') => i += 1,
+                // a label of the element like `x=expr` or `xs+=expr`
+                Tok::Ident(_) if tokens.get(i + 1).map(|t| &t.0) == Some(&Tok::Punct('=')) => i += 1,
+                Tok::Ident(_) if tokens.get(i + 1).map(|t| &t.0) == Some(&Tok::Punct('+'))
+                    && tokens.get(i + 2).map(|t| &t.0) == Some(&Tok::Punct('=')) =>
+                {
+                    i += 2
+                }
+                Tok::Ident(s) if s == "EOF" => {}
+                Tok::Ident(s) => alternatives.last_mut().unwrap().push(s.clone()),
+                Tok::Literal(s) => alternatives.last_mut().unwrap().push(interop::terminal(s)),
+                // an action, or a predicate followed by `?`
+                Tok::Block => {
+                    if tokens.get(i + 1).map(|t| &t.0) == Some(&Tok::Punct('?')) {
+                        i += 1;
+                    }
+                }
+                Tok::Tag(_) => {}
+                Tok::Punct(c) if "()?*+~.".contains(*c) => {
+                    return Err(format!(
+                        "line {}: the operator '{}' of EBNF is not supported, expand it into rules",
+                        line, c
+                    ));
+                }
+                tok => return Err(format!("line {}: unexpected {} in the rule of '{}'", line, tok, left)),
+            }
+            i += 1;
+        }
+        for (k, right) in alternatives.iter().enumerate() {
+            let right: Vec<&str> = right.iter().map(|s| s.as_str()).collect();
+            builder = if k == 0 { builder.rule(&left, &right) } else { builder.alt(&right) };
+        }
+    }
+    builder.build()
+}
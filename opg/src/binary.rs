@@ -0,0 +1,310 @@
+//! # binary
+//!
+//! `binary` writes an `OpTable` into a compact binary
+//! file, and loads it without copying for the runtime.
+//!
+//! ## Format
+//! All the integers are little-endian.
+//!
+//! | size | content |
+//! | --- | --- |
+//! | 4 | the magic `OPGT` |
+//! | 2 | the version, `VERSION` |
+//! | 2 | reserved, 0 |
+//! | 4 | `n`, the number of the terminals |
+//! | 4 | `m`, the number of the conflicts |
+//! | 4 | `s`, the bytes of the names |
+//! | 4(n + 1) | the offsets of the names, from 0 to `s` |
+//! | s | the names in UTF-8, sorted and unique |
+//! | ⌈n² / 4⌉ | the matrix, 2 bits for a cell |
+//! | 12m | the conflicts |
+//! | 4 | the CRC-32 of all the bytes above |
+//!
+//! The cell of row `i` and column `j` is at bit `2(in + j)`,
+//! where 0 is no relation, and 1, 2, 3 are `<`, `=`, `>`.
+//! A conflict is the rows of `a` and `b` in 4 bytes each,
+//! then `kept` and `rejected` in 1 byte each, and 2 bytes of 0.
+
+use crate::table::{OpTable, Relations};
+
+/// The magic number at the beginning.
+pub const MAGIC: [u8; 4] = *b"OPGT";
+
+/// The version of the format.
+pub const VERSION: u16 = 1;
+
+const HEADER: usize = 20;
+const CONFLICT: usize = 12;
+
+fn code(c: Option<char>) -> u8 {
+    match c {
+        Some('<') => 1,
+        Some('=') => 2,
+        Some('>') => 3,
+        _ => 0,
+    }
+}
+
+fn relation(code: u8) -> Option<char> {
+    match code {
+        1 => Some('<'),
+        2 => Some('='),
+        3 => Some('>'),
+        _ => None,
+    }
+}
+
+///
+/// The CRC-32 of IEEE 802.3, as in zip and PNG.
+///
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in bytes {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn push_u32(bytes: &mut Vec<u8>, x: usize) {
+    bytes.extend_from_slice(&(x as u32).to_le_bytes());
+}
+
+///
+/// Write the table in the binary format.
+///
+/// ## Example
+/// ```
+/// fs::write("table.bin", binary::write(&table))?;
+/// ```
+///
+pub fn write(table: &OpTable) -> Vec<u8> {
+    let mut ts: Vec<&String> = table.terminals().iter().collect();
+    ts.sort();
+    let n = ts.len();
+    let index = |t: &str| ts.iter().position(|v| *v == t).unwrap();
+
+    let mut bytes: Vec<u8> = Vec::new();
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    push_u32(&mut bytes, n);
+    push_u32(&mut bytes, table.conflicts().len());
+    push_u32(&mut bytes, ts.iter().map(|t| t.len()).sum());
+    let mut offset = 0;
+    push_u32(&mut bytes, offset);
+    for t in ts.iter() {
+        offset += t.len();
+        push_u32(&mut bytes, offset);
+    }
+    for t in ts.iter() {
+        bytes.extend_from_slice(t.as_bytes());
+    }
+    let mut matrix = vec![0u8; (n * n).div_ceil(4)];
+    for (i, a) in ts.iter().enumerate() {
+        for (j, b) in ts.iter().enumerate() {
+            let k = i * n + j;
+            matrix[k / 4] |= code(table.get(a, b)) << (2 * (k % 4));
+        }
+    }
+    bytes.extend_from_slice(&matrix);
+    for (a, b, kept, rejected) in table.conflicts() {
+        push_u32(&mut bytes, index(a));
+        push_u32(&mut bytes, index(b));
+        bytes.extend_from_slice(&[code(Some(*kept)), code(Some(*rejected)), 0, 0]);
+    }
+    let crc = crc32(&bytes);
+    bytes.extend_from_slice(&crc.to_le_bytes());
+    bytes
+}
+
+///
+/// A table loaded from the binary format,
+/// which borrows the bytes without copying.
+///
+pub struct BinaryTable<'a> {
+    /// the number of the terminals.
+    n: usize,
+    /// the offsets of the names.
+    offsets: &'a [u8],
+    /// the names.
+    names: &'a [u8],
+    matrix: &'a [u8],
+    conflicts: &'a [u8],
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> usize {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]) as usize
+}
+
+impl<'a> BinaryTable<'a> {
+    ///
+    /// Load the table from the bytes.
+    ///
+    /// ## Return
+    /// The table, or the error if the bytes are not
+    /// a valid table of the supported version.
+    ///
+    /// ## Example
+    /// ```
+    /// let bytes = fs::read("table.bin")?;
+    /// let table = binary::BinaryTable::load(&bytes)?;
+    /// let value = parser::parse(&table, &productions, &nts, &tokens, &mut semantic::Evaluator)?;
+    /// ```
+    ///
+    /// ## Principles
+    /// Check the magic, the version, the lengths and the
+    /// checksum, then the offsets should be ascending within
+    /// the names, and the names should be valid UTF-8 and
+    /// strictly ascending, so that `get()` could find them
+    /// by the binary search.
+    ///
+    pub fn load(bytes: &'a [u8]) -> Result<BinaryTable<'a>, String> {
+        if bytes.len() < HEADER + 8 || bytes[..4] != MAGIC {
+            return Err("not a table of opg".to_string());
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != VERSION {
+            return Err(format!("unsupported version {}, expected {}", version, VERSION));
+        }
+        let (n, m, s) = (read_u32(bytes, 8), read_u32(bytes, 12), read_u32(bytes, 16));
+        let cells = n.checked_mul(n).ok_or("too many terminals")?.div_ceil(4);
+        let expected = [HEADER, 4 * (n + 1), s, cells, CONFLICT * m, 4]
+            .iter()
+            .try_fold(0usize, |sum, x| sum.checked_add(*x));
+        match expected {
+            Some(len) if len == bytes.len() => {}
+            Some(len) => return Err(format!("the length should be {}, found {}", len, bytes.len())),
+            None => return Err("the lengths overflow".to_string()),
+        }
+        let body = bytes.len() - 4;
+        if crc32(&bytes[..body]) != read_u32(bytes, body) as u32 {
+            return Err("checksum mismatch".to_string());
+        }
+
+        let mut at = HEADER;
+        let mut take = |len: usize| {
+            at += len;
+            &bytes[at - len..at]
+        };
+        let table = BinaryTable {
+            n,
+            offsets: take(4 * (n + 1)),
+            names: take(s),
+            matrix: take(cells),
+            conflicts: take(CONFLICT * m),
+        };
+        if read_u32(table.offsets, 0) != 0 || read_u32(table.offsets, 4 * n) != s {
+            return Err("the offsets of the names should be from 0 to the end".to_string());
+        }
+        let mut last: Option<&str> = None;
+        for i in 0..n {
+            let (start, end) = (read_u32(table.offsets, 4 * i), read_u32(table.offsets, 4 * i + 4));
+            // the offsets in the middle are not bounded by the checks above
+            let bytes = match table.names.get(start..end) {
+                Some(bytes) => bytes,
+                None if start > end => return Err(format!("the offsets of the name {} are decreasing", i)),
+                None => return Err(format!("the offsets of the name {} are beyond the names", i)),
+            };
+            let name = std::str::from_utf8(bytes).map_err(|_| format!("the name {} is not UTF-8", i))?;
+            if last.is_some_and(|last| last >= name) {
+                return Err(format!("the names are not sorted at '{}'", name));
+            }
+            last = Some(name);
+        }
+        for k in 0..m {
+            let c = &table.conflicts[CONFLICT * k..CONFLICT * (k + 1)];
+            if read_u32(c, 0) >= n || read_u32(c, 4) >= n || relation(c[8]).is_none() || relation(c[9]).is_none() {
+                return Err(format!("the conflict {} is not valid", k));
+            }
+        }
+        Ok(table)
+    }
+
+    ///
+    /// The name of the terminal `i` in the sorted order.
+    ///
+    pub fn symbol(&self, i: usize) -> &'a str {
+        let (start, end) = (read_u32(self.offsets, 4 * i), read_u32(self.offsets, 4 * i + 4));
+        // checked by `load()`
+        std::str::from_utf8(&self.names[start..end]).unwrap()
+    }
+
+    ///
+    /// Find the terminal by the binary search.
+    ///
+    pub fn index(&self, t: &str) -> Option<usize> {
+        let (mut low, mut high) = (0, self.n);
+        while low < high {
+            let mid = (low + high) / 2;
+            match self.symbol(mid).cmp(t) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+
+    ///
+    /// Query the relation between
+    /// terminal `a` on the left and
+    /// terminal `b` on the right.
+    ///
+    /// ## Return
+    /// One of `<`, `=`, `>` or `None`
+    /// if the pair has no relation.
+    ///
+    pub fn get(&self, a: &str, b: &str) -> Option<char> {
+        let k = self.index(a)? * self.n + self.index(b)?;
+        relation((self.matrix[k / 4] >> (2 * (k % 4))) & 3)
+    }
+
+    ///
+    /// The terminals of the table in the sorted order.
+    ///
+    pub fn terminals(&self) -> impl Iterator<Item = &'a str> + '_ {
+        (0..self.n).map(move |i| self.symbol(i))
+    }
+
+    ///
+    /// The conflicts as `(a, b, kept, rejected)`,
+    /// see `OpTable::conflicts()`.
+    ///
+    pub fn conflicts(&self) -> impl Iterator<Item = (&'a str, &'a str, char, char)> + '_ {
+        self.conflicts.chunks(CONFLICT).map(move |c| {
+            let (a, b) = (self.symbol(read_u32(c, 0)), self.symbol(read_u32(c, 4)));
+            (a, b, relation(c[8]).unwrap(), relation(c[9]).unwrap())
+        })
+    }
+
+    ///
+    /// Copy the table into an `OpTable`.
+    ///
+    pub fn to_table(&self) -> OpTable {
+        let mut table = OpTable::new(self.terminals().map(|t| t.to_string()).collect());
+        for a in self.terminals() {
+            for b in self.terminals() {
+                if let Some(c) = self.get(a, b) {
+                    table.insert(&(a.to_string(), b.to_string()), c);
+                }
+            }
+        }
+        for (a, b, _, rejected) in self.conflicts() {
+            table.insert(&(a.to_string(), b.to_string()), rejected);
+        }
+        table
+    }
+}
+
+impl Relations for BinaryTable<'_> {
+    fn get(&self, a: &str, b: &str) -> Option<char> {
+        BinaryTable::get(self, a, b)
+    }
+
+    fn contains(&self, t: &str) -> bool {
+        self.index(t).is_some()
+    }
+}
@@ -0,0 +1,316 @@
+//! # bison
+//!
+//! `bison` exports the grammar as a bison `.y` file
+//! with the precedence declarations, and imports the
+//! rule section of a simple `.y` file.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::codegen::terminal_names;
+use crate::grammar::GrammarBuilder;
+use crate::interop::{self, Tok};
+use crate::pratt::{self, Fixity};
+use crate::table::OpTable;
+use crate::{get_non_terminals, ordered_symbols, Assoc, Production};
+
+///
+/// The precedence levels from the lowest,
+/// as the lines of `%left`, `%right` and `%nonassoc`.
+///
+pub type Levels = Vec<(Assoc, Vec<String>)>;
+
+///
+/// Arrange the precedence from `gen_precedence()` in levels.
+///
+pub fn declared_levels(prec: &HashMap<String, (usize, Assoc)>) -> Levels {
+    let mut declared: Vec<(&String, &(usize, Assoc))> = prec.iter().collect();
+    declared.sort_by_key(|(t, (level, _))| (*level, (*t).clone()));
+    let mut levels: Levels = Vec::new();
+    let mut last = None;
+    for (t, (level, assoc)) in declared {
+        if last != Some(*level) {
+            levels.push((*assoc, Vec::new()));
+            last = Some(*level);
+        }
+        levels.last_mut().unwrap().1.push(t.clone());
+    }
+    levels
+}
+
+///
+/// Derive the precedence levels of the infix operators
+/// from the table.
+///
+/// ## Input
+/// `table` the `OpTable` of the grammar.
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `tv` the terminals in order.
+///
+/// ## Return
+/// The levels from the lowest, where the operators
+/// which are not consistent with the table are left out.
+///
+/// ## Principles
+/// 1. Infer the binding powers by `pratt::infer()`, and put the
+///    infix operators with the same powers and associativity
+///    in a level, ordered by the left power.
+/// 2. In yacc, `a > b` if `a` is in a higher level, `a < b`
+///    if in a lower level, and in the same level it is given
+///    by the associativity. An operator is left out if its
+///    relation to another operator is not as such.
+///
+pub fn derive_levels(table: &OpTable, productions: &[Production], nts: &HashSet<String>, tv: &[String]) -> Levels {
+    let powers = pratt::infer(table, productions, nts, tv);
+    let mut ops: Vec<(String, (usize, usize), Assoc)> = Vec::new();
+    for o in powers.operators.iter().filter(|o| o.fixity == Fixity::Infix) {
+        if let (Some(l), Some(r), Some(assoc)) = (o.left, o.right, o.assoc) {
+            ops.push((o.name.clone(), (l, r), assoc));
+        }
+    }
+    let mut keys: Vec<((usize, usize), Assoc)> = Vec::new();
+    for (_, powers, assoc) in ops.iter() {
+        if !keys.contains(&(*powers, *assoc)) {
+            keys.push((*powers, *assoc));
+        }
+    }
+    keys.sort_by_key(|(powers, _)| *powers);
+    let level = |powers, assoc| keys.iter().position(|k| *k == (powers, assoc)).unwrap();
+
+    let mut inconsistent: HashSet<String> = HashSet::new();
+    for (a, pa, assoc) in ops.iter() {
+        for (b, pb, assoc_b) in ops.iter() {
+            let expected = match level(*pa, *assoc).cmp(&level(*pb, *assoc_b)) {
+                Ordering::Greater => Some('>'),
+                Ordering::Less => Some('<'),
+                Ordering::Equal => match assoc {
+                    Assoc::Left => Some('>'),
+                    Assoc::Right => Some('<'),
+                    Assoc::Nonassoc => None,
+                },
+            };
+            if table.get(a, b) != expected {
+                inconsistent.insert(a.clone());
+                inconsistent.insert(b.clone());
+            }
+        }
+    }
+    let mut levels: Levels = Vec::new();
+    for (i, (_, assoc)) in keys.iter().enumerate() {
+        let level: Vec<String> = ops
+            .iter()
+            .filter(|(t, p, a)| level(*p, *a) == i && !inconsistent.contains(t))
+            .map(|(t, _, _)| t.clone())
+            .collect();
+        if !level.is_empty() {
+            levels.push((*assoc, level));
+        }
+    }
+    levels
+}
+
+///
+/// Name a terminal in the UPPER_SNAKE case.
+///
+fn token_name(camel: &str) -> String {
+    let mut name = String::new();
+    let mut last_lower = false;
+    for c in camel.chars() {
+        if c.is_ascii_uppercase() && last_lower {
+            name.push('_');
+        }
+        last_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+///
+/// Spell a terminal of a single character
+/// as a character literal in bison.
+///
+fn char_literal(t: &str) -> Option<String> {
+    let mut cs = t.chars();
+    match (cs.next(), cs.next()) {
+        (Some('\''), None) => Some("'\\''".to_string()),
+        (Some('\\'), None) => Some("'\\\\'".to_string()),
+        (Some(c), None) if c.is_ascii_graphic() => Some(format!("'{}'", c)),
+        _ => None,
+    }
+}
+
+///
+/// Export the grammar as a bison `.y` file.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `levels` the precedence levels from the lowest, which
+/// could be from `derive_levels()` or `declared_levels()`.
+///
+/// ## Return
+/// The declarations and the rule section, where the
+/// semantic actions of `opg` are left out.
+///
+/// ## Example
+/// ```
+/// let levels = bison::derive_levels(&table, &productions, &nts, &tv);
+/// fs::write("grammar.y", bison::export(&productions, &levels))?;
+/// ```
+///
+/// ## Principles
+/// A terminal of a single character is written as a
+/// character literal like `'+'`, and the others are
+/// declared as `%token NAME "text"` so that the text
+/// is kept for `import()`. The non-terminals are named
+/// as identifiers, and `ε` is written as `%empty`.
+///
+pub fn export(productions: &[Production], levels: &[(Assoc, Vec<String>)]) -> String {
+    let nts = get_non_terminals(productions);
+    let (nv, tv) = ordered_symbols(productions, &nts);
+    let mut used: HashSet<String> = HashSet::new();
+    let mut names: HashMap<String, String> = HashMap::new();
+    for v in nv.iter() {
+        names.insert(v.clone(), interop::unique(interop::identifier(v), &mut used));
+    }
+    let mut output = String::from("/* Generated by opg */\n\n");
+    let mut tokens = String::new();
+    for (t, name) in tv.iter().zip(terminal_names(&tv)) {
+        let spelling = match char_literal(t) {
+            Some(c) => c,
+            None => {
+                let name = interop::unique(token_name(&name), &mut used);
+                tokens += &format!("%token {} \"{}\"\n", name, t.replace('\\', "\\\\").replace('"', "\\\""));
+                name
+            }
+        };
+        names.insert(t.clone(), spelling);
+    }
+    if !tokens.is_empty() {
+        output += &tokens;
+        output += "\n";
+    }
+    if !levels.is_empty() {
+        for (assoc, level) in levels {
+            let directive = match assoc {
+                Assoc::Left => "%left",
+                Assoc::Right => "%right",
+                Assoc::Nonassoc => "%nonassoc",
+            };
+            let level: Vec<&str> = level.iter().filter_map(|t| names.get(t)).map(|t| t.as_str()).collect();
+            output += &format!("{} {}\n", directive, level.join(" "));
+        }
+        output += "\n";
+    }
+    if let Some(p) = productions.first() {
+        output += &format!("%start {}\n\n", names[&p.left]);
+    }
+    output += "%%\n";
+    for v in nv.iter() {
+        output += &format!("\n{}:\n", names[v]);
+        for (i, p) in productions.iter().filter(|p| p.left == *v).enumerate() {
+            let right: Vec<&str> = p.right.iter().map(|s| names[s].as_str()).collect();
+            let right = if right.is_empty() { "%empty".to_string() } else { right.join(" ") };
+            output += &format!("{} {}\n", if i == 0 { " " } else { "|" }, right);
+        }
+        output += ";\n";
+    }
+    output
+}
+
+///
+/// Import the rule section of a bison `.y` file.
+///
+/// ## Return
+/// The productions, or the error with the line number.
+///
+/// ## Principles
+/// 1. The declarations before the first `%%` give the texts
+///    of the tokens by `%token NAME "text"` and the starting
+///    non-terminal by `%start`.
+/// 2. The rules between the first and the second `%%` are
+///    `left: right | right ... ;`, where the token names are
+///    replaced by their texts, the literals are the terminals,
+///    and the actions, `%empty` and `%prec X` are skipped.
+/// 3. The productions are built by `GrammarBuilder`.
+///
+pub fn import(text: &str) -> Result<Vec<Production>, String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let marks: Vec<usize> = (0..lines.len()).filter(|i| lines[*i].trim() == "%%").collect();
+    let first = *marks.first().ok_or("no '%%' before the rules")?;
+    let last = marks.get(1).copied().unwrap_or(lines.len());
+
+    let mut texts: HashMap<String, String> = HashMap::new();
+    let mut start: Option<String> = None;
+    let declarations = interop::scan(&lines[..first].join("\n"))?;
+    let mut directive = String::new();
+    for (k, (tok, _)) in declarations.iter().enumerate() {
+        match tok {
+            Tok::Directive(d) => directive = d.clone(),
+            Tok::Ident(name) if directive == "%start" => start = Some(name.clone()),
+            Tok::Ident(name) if directive == "%token" => {
+                // `%token NAME [number] "text"`
+                let mut next = declarations.get(k + 1).map(|t| &t.0);
+                if let Some(Tok::Ident(n)) = next {
+                    if n.chars().all(|c| c.is_ascii_digit()) {
+                        next = declarations.get(k + 2).map(|t| &t.0);
+                    }
+                }
+                if let Some(Tok::Literal(s)) = next {
+                    if !s.is_empty() && !s.contains(char::is_whitespace) {
+                        texts.insert(name.clone(), s.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // the lines of the tokens start from the rule section
+    let rules: Vec<(Tok, usize)> = interop::scan(&lines[first + 1..last].join("\n"))?
+        .into_iter()
+        .map(|(tok, line)| (tok, line + first + 1))
+        .collect();
+    let symbol = |name: &str| texts.get(name).cloned().unwrap_or_else(|| name.to_string());
+    let mut builder = GrammarBuilder::new();
+    let mut i = 0;
+    while i < rules.len() {
+        let left = match (&rules[i], rules.get(i + 1)) {
+            ((Tok::Ident(left), _), Some((Tok::Punct(':'), _))) => left.clone(),
+            ((tok, line), _) => return Err(format!("line {}: expected a rule, found {}", line, tok)),
+        };
+        i += 2;
+        let mut alternatives: Vec<Vec<String>> = vec![Vec::new()];
+        while i < rules.len() {
+            let (tok, line) = &rules[i];
+            match tok {
+                Tok::Ident(_) if matches!(rules.get(i + 1), Some((Tok::Punct(':'), _))) => break,
+                Tok::Punct(';') => {
+                    i += 1;
+                    break;
+                }
+                Tok::Punct('|') => alternatives.push(Vec::new()),
+                Tok::Ident(name) => alternatives.last_mut().unwrap().push(symbol(name)),
+                Tok::Literal(s) => alternatives.last_mut().unwrap().push(interop::terminal(s)),
+                Tok::Directive(d) if d == "%prec" || d == "%dprec" || d == "%merge" => i += 1,
+                Tok::Directive(d) if d == "%empty" => {}
+                Tok::Block | Tok::Set(_) | Tok::Tag(_) => {}
+                tok => return Err(format!("line {}: unexpected {} in the rule of '{}'", line, tok, left)),
+            }
+            i += 1;
+        }
+        for (k, right) in alternatives.iter().enumerate() {
+            let right: Vec<&str> = right.iter().map(|s| s.as_str()).collect();
+            builder = if k == 0 { builder.rule(&left, &right) } else { builder.alt(&right) };
+        }
+    }
+    if let Some(start) = start {
+        builder = builder.start(&start);
+    }
+    builder.build()
+}
@@ -0,0 +1,76 @@
+//! # build
+//!
+//! `build` generates the parser of a grammar
+//! file at build time, to be called in `build.rs`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::compress::Layout;
+use crate::{check_grammar, codegen, gen_productions, gen_table, get_non_terminals, ordered_symbols, table, Production};
+
+///
+/// Generate the Rust parser of the productions
+/// by `codegen::rust()`.
+///
+/// ## Return
+/// The source code, or the precedence conflicts
+/// one in a line if the grammar is ambiguous.
+///
+pub fn generate_code(productions: &[Production]) -> Result<String, String> {
+    if productions.is_empty() {
+        return Err("the grammar is empty".to_string());
+    }
+    let table = gen_table(productions);
+    if !table.conflicts().is_empty() {
+        let lines: Vec<String> = table.conflicts().iter().map(table::format_conflict).collect();
+        return Err(lines.join("\n"));
+    }
+    let nts = get_non_terminals(productions);
+    let (_, mut tv) = ordered_symbols(productions, &nts);
+    tv.push("$".to_string());
+    Ok(codegen::rust(&table, productions, &nts, &tv, Layout::Dense))
+}
+
+///
+/// Generate the Rust parser of a grammar file into `OUT_DIR`.
+///
+/// ## Input
+/// `grammar` the path of the grammar file.
+///
+/// `name` the name of the output file without `.rs`.
+///
+/// ## Return
+/// The path of the output file, or the error.
+///
+/// ## Example
+/// ```
+/// // build.rs
+/// fn main() {
+///     opg::build::generate("grammar.txt", "expr").unwrap();
+/// }
+///
+/// // src/main.rs
+/// mod expr {
+///     include!(concat!(env!("OUT_DIR"), "/expr.rs"));
+/// }
+/// ```
+///
+/// ## Principles
+/// Check the file by `check_grammar()`, parse it by
+/// `gen_productions()`, and generate the code by
+/// `generate_code()`. Cargo is told to run the build
+/// script again when the grammar file changes.
+///
+pub fn generate<P: AsRef<Path>>(grammar: P, name: &str) -> Result<PathBuf, String> {
+    let grammar = grammar.as_ref();
+    println!("cargo:rerun-if-changed={}", grammar.display());
+    let contents = fs::read_to_string(grammar).map_err(|e| format!("{}: {}", grammar.display(), e))?;
+    check_grammar(&contents).map_err(|e| format!("{}: {}", grammar.display(), e))?;
+    let code = generate_code(&gen_productions(&contents)).map_err(|e| format!("{}: {}", grammar.display(), e))?;
+    let dir = env::var("OUT_DIR").map_err(|_| "OUT_DIR is not set, call it in build.rs".to_string())?;
+    let path = Path::new(&dir).join(format!("{}.rs", name));
+    fs::write(&path, code).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
@@ -0,0 +1,194 @@
+//! # check
+//!
+//! `check` validates an `OpTable` against its grammar
+//! by the generated sentences and their mutations,
+//! with the Earley parser as the oracle.
+
+use std::collections::HashSet;
+
+use crate::earley::Earley;
+use crate::lexer::Token;
+use crate::parser::{self, Recognizer};
+use crate::sentence::{Generator, Rng};
+use crate::table::OpTable;
+use crate::Production;
+
+///
+/// Decide whether the operator precedence parsing
+/// accepts the terminals, without `$`.
+///
+pub fn op_accepts(table: &OpTable, productions: &[Production], nts: &HashSet<String>, sentence: &[String]) -> bool {
+    let tokens: Vec<Token> = sentence
+        .iter()
+        .map(|t| Token::new(t))
+        .chain(std::iter::once(Token::new("$")))
+        .collect();
+    parser::parse(table, productions, nts, &tokens, &mut Recognizer).is_ok()
+}
+
+///
+/// Decide whether the grammar derives the terminals
+/// by `Earley::recognize()`.
+///
+pub fn oracle(productions: &[Production], nts: &HashSet<String>, sentence: &[String]) -> bool {
+    Earley::new(productions, nts).recognize(sentence)
+}
+
+///
+/// Mutate the sentence by deleting, inserting,
+/// replacing or swapping a random token.
+///
+pub fn mutate(sentence: &[String], ts: &[String], rng: &mut Rng) -> Vec<String> {
+    let mut s = sentence.to_vec();
+    let n = s.len();
+    match rng.below(4) {
+        0 if n > 0 => {
+            s.remove(rng.below(n));
+        }
+        2 if n > 0 => {
+            s[rng.below(n)] = ts[rng.below(ts.len())].clone();
+        }
+        3 if n > 1 => {
+            let k = rng.below(n - 1);
+            s.swap(k, k + 1);
+        }
+        _ => s.insert(rng.below(n + 1), ts[rng.below(ts.len())].clone()),
+    }
+    s
+}
+
+///
+/// Shrink the sentence while `fails` still holds.
+///
+/// ## Principles
+/// Try removing every span of one or two tokens, and
+/// replacing every token with another terminal which is
+/// earlier in `ts`. Keep the first try which still fails,
+/// and repeat until nothing changes.
+///
+pub fn shrink<F>(sentence: &[String], ts: &[String], fails: F) -> Vec<String>
+where
+    F: Fn(&[String]) -> bool,
+{
+    let mut s = sentence.to_vec();
+    'shrink: loop {
+        for width in [2, 1] {
+            for k in 0..(s.len() + 1).saturating_sub(width) {
+                let mut t = s.clone();
+                t.drain(k..k + width);
+                if fails(&t) {
+                    s = t;
+                    continue 'shrink;
+                }
+            }
+        }
+        for k in 0..s.len() {
+            let rank = ts.iter().position(|t| *t == s[k]).unwrap_or(ts.len());
+            for t in ts[..rank].iter() {
+                let mut u = s.clone();
+                u[k] = t.clone();
+                if fails(&u) {
+                    s = u;
+                    continue 'shrink;
+                }
+            }
+        }
+        return s;
+    }
+}
+
+///
+/// The result of the check.
+///
+#[derive(Default)]
+pub struct Report {
+    /// the number of the sentences.
+    pub sentences: usize,
+    /// the number of the mutations.
+    pub mutations: usize,
+    /// the sentences rejected by the table,
+    /// with the minimal counterexamples.
+    pub rejected: Vec<(Vec<String>, Vec<String>)>,
+    /// the non-sentences accepted by the table,
+    /// with the minimal counterexamples.
+    pub accepted: Vec<(Vec<String>, Vec<String>)>,
+}
+
+fn push_case(cases: &mut Vec<(Vec<String>, Vec<String>)>, s: Vec<String>, minimal: Vec<String>) {
+    if !cases.iter().any(|(_, m)| *m == minimal) {
+        cases.push((s, minimal));
+    }
+}
+
+///
+/// Check the table with the generated sentences.
+///
+/// ## Input
+/// `table` the `OpTable` of the grammar.
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `generator` the sentence generator of the grammar.
+///
+/// `count` the number of the sentences.
+///
+/// `rng` the random generator.
+///
+/// ## Return
+/// The `Report`, where every counterexample
+/// is only kept once.
+///
+/// ## Example
+/// ```
+/// let generator = sentence::Generator::new(&productions, &nts)?;
+/// let report = check::check(&table, &productions, &nts, &generator, 1000, &mut sentence::Rng::new(42));
+/// ```
+///
+/// ## Principles
+/// Every generated sentence should be accepted by the table.
+/// Every mutation is decided by `oracle()`,
+/// and the table should agree. A disagreement is shrunk
+/// by `shrink()` into a minimal one of the same kind.
+///
+pub fn check(
+    table: &OpTable,
+    productions: &[Production],
+    nts: &HashSet<String>,
+    generator: &Generator,
+    count: usize,
+    rng: &mut Rng,
+) -> Report {
+    let mut ts: Vec<String> = table.terminals().iter().filter(|t| *t != "$").cloned().collect();
+    ts.sort();
+    let op = |s: &[String]| op_accepts(table, productions, nts, s);
+
+    let mut report = Report::default();
+    for _ in 0..count {
+        let s = generator.sentence(rng);
+        report.sentences += 1;
+        if !op(&s) {
+            let minimal = shrink(&s, &ts, |t| !op(t) && oracle(productions, nts, t));
+            push_case(&mut report.rejected, s.clone(), minimal);
+        }
+        if ts.is_empty() {
+            continue;
+        }
+        let m = mutate(&s, &ts, rng);
+        report.mutations += 1;
+        let expected = oracle(productions, nts, &m);
+        if op(&m) == expected {
+            continue;
+        }
+        let fails = |t: &[String]| oracle(productions, nts, t) == expected && op(t) != expected;
+        let minimal = shrink(&m, &ts, fails);
+        if expected {
+            push_case(&mut report.rejected, m, minimal);
+        } else {
+            push_case(&mut report.accepted, m, minimal);
+        }
+    }
+    report
+}
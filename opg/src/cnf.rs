@@ -0,0 +1,176 @@
+//! # cnf
+//!
+//! `cnf` converts a grammar into
+//! the Chomsky normal form step by step.
+
+use std::collections::HashSet;
+
+use crate::hygiene;
+use crate::ll1;
+use crate::{fresh_symbol, Production};
+
+fn push_unique(productions: &mut Vec<Production>, p: Production) {
+    if !productions.iter().any(|q| q.left == p.left && q.right == p.right) {
+        productions.push(p);
+    }
+}
+
+///
+/// Convert the grammar into the Chomsky normal form.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// ## Return
+/// The name and the productions after every step,
+/// the last one is in the Chomsky normal form, where every
+/// production is `A -> B C`, `A -> a` or `S0 -> ε`.
+///
+/// ## Example
+/// ```
+/// let steps = cnf::to_cnf(&productions, &nts);
+/// let cnf = &steps.last().unwrap().1;
+/// ```
+///
+/// ## Principles
+/// 1. START: add `S0 -> S` for the new starting non-terminal.
+/// 2. TERM: replace the terminal `a` in a right side longer
+///    than 1 with a new non-terminal `T_a -> a`.
+/// 3. BIN: split `A -> X1 X2 ... Xn` into
+///    `A -> X1 A_1`, `A_1 -> X2 A_2`, ..., `A_n-2 -> Xn-1 Xn`.
+/// 4. DEL: remove the ε productions by adding the
+///    right sides without the nullable symbols.
+/// 5. UNIT: replace `A -> B` with the productions of B.
+/// 6. CLEAN: remove the non-terminals which derive nothing
+///    or could not be reached from `S0`.
+///
+pub fn to_cnf(productions: &[Production], nts: &HashSet<String>) -> Vec<(String, Vec<Production>)> {
+    let mut steps: Vec<(String, Vec<Production>)> = Vec::new();
+    let mut nts = nts.clone();
+    let mut used: HashSet<String> = nts.clone();
+    for p in productions {
+        used.extend(p.right.iter().cloned());
+    }
+
+    // START
+    let start = fresh_symbol(&format!("{}0", productions[0].left), &used);
+    used.insert(start.clone());
+    nts.insert(start.clone());
+    let mut ps: Vec<Production> = vec![Production::new(&start, vec![productions[0].left.clone()])];
+    ps.extend(productions.iter().map(|p| Production::new(&p.left, p.right.clone())));
+    steps.push(("START".to_string(), ps.clone()));
+
+    // TERM
+    let mut terms: Vec<Production> = Vec::new();
+    for p in ps.iter_mut() {
+        if p.right.len() < 2 {
+            continue;
+        }
+        for v in p.right.iter_mut() {
+            if nts.contains(v) {
+                continue;
+            }
+            let t = match terms.iter().find(|q| q.right[0] == *v) {
+                Some(q) => q.left.clone(),
+                None => {
+                    let t = fresh_symbol(&format!("T_{}", v), &used);
+                    used.insert(t.clone());
+                    nts.insert(t.clone());
+                    terms.push(Production::new(&t, vec![v.clone()]));
+                    t
+                }
+            };
+            *v = t;
+        }
+    }
+    ps.extend(terms);
+    steps.push(("TERM".to_string(), ps.clone()));
+
+    // BIN
+    let mut bin: Vec<Production> = Vec::new();
+    for p in ps {
+        let mut left = p.left.clone();
+        let mut right = p.right.clone();
+        let mut k = 0;
+        while right.len() > 2 {
+            k += 1;
+            let next = fresh_symbol(&format!("{}_{}", p.left, k), &used);
+            used.insert(next.clone());
+            nts.insert(next.clone());
+            let rest = right.split_off(1);
+            bin.push(Production::new(&left, vec![right[0].clone(), next.clone()]));
+            left = next;
+            right = rest;
+        }
+        bin.push(Production::new(&left, right));
+    }
+    steps.push(("BIN".to_string(), bin.clone()));
+
+    // DEL
+    let nullable = ll1::gen_nullable(&bin);
+    let mut del: Vec<Production> = Vec::new();
+    for p in bin.iter() {
+        let positions: Vec<usize> = (0..p.right.len()).filter(|i| nullable.contains(&p.right[*i])).collect();
+        for mask in 0..(1usize << positions.len()) {
+            let right: Vec<String> = p
+                .right
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| match positions.iter().position(|x| x == i) {
+                    Some(bit) => mask & (1 << bit) == 0,
+                    None => true,
+                })
+                .map(|(_, v)| v.clone())
+                .collect();
+            if right.is_empty() && p.left != start {
+                continue;
+            }
+            if right.len() == 1 && right[0] == p.left {
+                continue;
+            }
+            push_unique(&mut del, Production::new(&p.left, right));
+        }
+    }
+    steps.push(("DEL".to_string(), del.clone()));
+
+    // UNIT
+    let is_unit = |p: &Production| p.right.len() == 1 && nts.contains(&p.right[0]);
+    let mut unit: Vec<Production> = Vec::new();
+    let mut lefts: Vec<String> = Vec::new();
+    for p in del.iter() {
+        if !lefts.contains(&p.left) {
+            lefts.push(p.left.clone());
+        }
+    }
+    for a in lefts.iter() {
+        // the non-terminals reached by unit productions
+        let mut reach: Vec<String> = vec![a.clone()];
+        let mut i = 0;
+        while i < reach.len() {
+            let b = reach[i].clone();
+            for p in del.iter().filter(|p| p.left == b && is_unit(p)) {
+                if !reach.contains(&p.right[0]) {
+                    reach.push(p.right[0].clone());
+                }
+            }
+            i += 1;
+        }
+        for b in reach.iter() {
+            for p in del.iter().filter(|p| p.left == *b && !is_unit(p)) {
+                push_unique(&mut unit, Production::new(a, p.right.clone()));
+            }
+        }
+    }
+    steps.push(("UNIT".to_string(), unit.clone()));
+
+    // CLEAN
+    let productive = hygiene::gen_productive(&unit, &nts);
+    unit.retain(|p| productive.contains(&p.left) && p.right.iter().all(|v| !nts.contains(v) || productive.contains(v)));
+    let reachable = hygiene::gen_reachable(&unit, &start);
+    unit.retain(|p| reachable.contains(&p.left));
+    steps.push(("CLEAN".to_string(), unit));
+    steps
+}
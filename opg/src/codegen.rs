@@ -0,0 +1,898 @@
+//! # codegen
+//!
+//! `codegen` emits the source code of a standalone
+//! operator precedence parser in Rust or C from an
+//! `OpTable`, which does not depend on `opg` at runtime.
+
+use std::collections::HashSet;
+
+use crate::compress::{self, Compressed, Layout, Merged, Packed};
+use crate::table::OpTable;
+use crate::{format_grammar, Production};
+
+///
+/// Name a terminal as an identifier in CamelCase,
+/// like `Plus` for `+` and `Id` for `id`.
+///
+fn terminal_name(t: &str) -> String {
+    if t == "$" {
+        return "End".to_string();
+    }
+    let mut name = String::new();
+    let mut upper = true;
+    for c in t.chars() {
+        let word = match c {
+            '+' => "Plus",
+            '-' => "Minus",
+            '*' => "Star",
+            '/' => "Slash",
+            '%' => "Percent",
+            '^' => "Caret",
+            '=' => "Eq",
+            '<' => "Lt",
+            '>' => "Gt",
+            '!' => "Bang",
+            '&' => "Amp",
+            '|' => "Pipe",
+            '(' => "LParen",
+            ')' => "RParen",
+            '[' => "LBracket",
+            ']' => "RBracket",
+            '{' => "LBrace",
+            '}' => "RBrace",
+            ',' => "Comma",
+            ';' => "Semi",
+            ':' => "Colon",
+            '.' => "Dot",
+            '?' => "Question",
+            '~' => "Tilde",
+            '#' => "Hash",
+            '@' => "At",
+            _ if c.is_ascii_alphanumeric() => {
+                name.push(if upper { c.to_ascii_uppercase() } else { c });
+                upper = false;
+                continue;
+            }
+            _ => "Sym",
+        };
+        name += word;
+        upper = true;
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, 'T');
+    }
+    name
+}
+
+///
+/// The results of the generated C parser, from `0` down.
+///
+const C_RESULTS: [&str; 6] = ["OK", "ERR_RELATION", "ERR_PHRASE", "ERR_PRODUCTION", "ERR_END", "ERR_MEMORY"];
+
+///
+/// Name the terminals by `name()`, appending the index
+/// to the repeated names, where `$` is named as `end`,
+/// and the names in `reserved` are not given to the others.
+///
+fn unique_names<F>(tv: &[String], end: &str, reserved: &[&str], name: F) -> Vec<String>
+where
+    F: Fn(&str) -> String,
+{
+    let mut names: Vec<String> = Vec::new();
+    let mut used: HashSet<String> = reserved.iter().map(|r| r.to_string()).collect();
+    // the driver refers to the end marker by its name
+    used.insert(end.to_string());
+    for (i, t) in tv.iter().enumerate() {
+        if t == "$" {
+            names.push(end.to_string());
+            continue;
+        }
+        let base = name(t);
+        let mut name = base.clone();
+        let mut k = i;
+        while !used.insert(name.clone()) {
+            name = format!("{}{}", base, k);
+            k += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+///
+/// Name the terminals by `terminal_name()`,
+/// appending the index to the repeated names.
+/// `End` is kept for `$`, so a terminal like
+/// `end` is named with the index, and so are the
+/// names of the keyword `Self` and the constants of `Token`.
+///
+pub fn terminal_names(tv: &[String]) -> Vec<String> {
+    unique_names(tv, "End", &["Self", "COUNT", "ALL"], terminal_name)
+}
+
+///
+/// Emit a standalone Rust module of the parser.
+///
+/// ## Input
+/// `table` the `OpTable` of the grammar.
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `tv` the terminals in order, ended with `$`.
+///
+/// `layout` the representation of the relation, see `relation()`.
+///
+/// ## Return
+/// The source code of the module, with
+/// - `Token`: the terminals, where `$` is `Token::End`.
+/// - `relation()`: the relation in the array `TABLE` for
+///   `Layout::Dense`, by the precedence functions `F` and `G`
+///   for `Layout::Functions`, in the merged `MATRIX` for
+///   `Layout::Merged`, or in the comb vector `SLOTS` for
+///   `Layout::Packed`. If the functions do not exist,
+///   the table is compressed by `OpTable::compress()`.
+/// - `PRODUCTIONS`: the skeletons of the productions,
+///   indexed as in the grammar.
+/// - `Reducer` and `parse()`: the driver as `parser::parse()`.
+///
+/// ## Example
+/// ```
+/// let code = codegen::rust(&table, &productions, &nts, &tv, Layout::Dense);
+/// fs::write("parser.rs", code).expect("Cannot output file!");
+/// ```
+///
+pub fn rust(
+    table: &OpTable,
+    productions: &[Production],
+    nts: &HashSet<String>,
+    tv: &[String],
+    layout: Layout,
+) -> String {
+    let names = terminal_names(tv);
+    let n = tv.len();
+    let mut output = String::new();
+    // plain comments, so that it could be included by `include!()`
+    output += "// Operator precedence parser generated by opg.\n//\n";
+    for line in format_grammar(productions).lines() {
+        output += &format!("// {}\n", line);
+    }
+    output += "\n";
+
+    // the token enum
+    output += "/// The terminals of the grammar, where `End` is `$`.\n";
+    output += "#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]\npub enum Token {\n";
+    for name in names.iter() {
+        output += &format!("    {},\n", name);
+    }
+    output += "}\n\n";
+    output += "impl Token {\n";
+    output += &format!("    pub const COUNT: usize = {};\n\n", n);
+    output += "    /// All the tokens in order.\n";
+    output += &format!("    pub const ALL: [Token; {}] = [", n);
+    let all: Vec<String> = names.iter().map(|name| format!("Token::{}", name)).collect();
+    output += &all.join(", ");
+    output += "];\n\n";
+    output += "    /// The terminal in the grammar.\n";
+    output += "    pub fn text(self) -> &'static str {\n        match self {\n";
+    for (t, name) in tv.iter().zip(names.iter()) {
+        output += &format!("            Token::{} => {:?},\n", name, t);
+    }
+    output += "        }\n    }\n\n";
+    output += "    /// The token of a terminal in the grammar.\n";
+    output += "    pub fn from_text(text: &str) -> Option<Token> {\n";
+    output += "        Token::ALL.iter().copied().find(|t| t.text() == text)\n    }\n}\n\n";
+
+    // the relation
+    output += "/// The precedence relation between two terminals.\n";
+    output += "#[derive(Clone, Copy, PartialEq, Eq, Debug)]\npub enum Relation {\n";
+    output += "    Less,\n    Equal,\n    Greater,\n    None,\n}\n\n";
+    output += &match layout {
+        Layout::Dense => rust_relation(table, tv),
+        Layout::Merged => rust_merged(&compress::merge(table, tv)),
+        Layout::Packed => rust_packed(&compress::pack(table, tv)),
+        Layout::Functions => match table.compress(tv) {
+            Compressed::Functions(f, g) => rust_functions(&f, &g),
+            Compressed::Merged(merged) => rust_merged(&merged),
+            Compressed::Packed(packed) => rust_packed(&packed),
+        },
+    };
+
+    // the productions
+    output += "/// A symbol in the right side of a production.\n";
+    output += "#[derive(Clone, Copy, PartialEq, Eq, Debug)]\npub enum Symbol {\n";
+    output += "    T(Token),\n    N(&'static str),\n}\n\n";
+    output += "/// The skeleton of a production.\n";
+    output += "pub struct Production {\n    pub left: &'static str,\n    pub right: &'static [Symbol],\n}\n\n";
+    output += "/// The productions, indexed as in the grammar.\n";
+    output += "pub const PRODUCTIONS: &[Production] = &[\n";
+    for p in productions {
+        let right: Vec<String> = p
+            .right
+            .iter()
+            .map(|v| match tv.iter().position(|t| t == v) {
+                Some(i) if !nts.contains(v) => format!("Symbol::T(Token::{})", names[i]),
+                _ => format!("Symbol::N({:?})", v),
+            })
+            .collect();
+        output += &format!(
+            "    // {}\n    Production {{\n        left: {:?},\n        right: &[{}],\n    }},\n",
+            p,
+            p.left,
+            right.join(", ")
+        );
+    }
+    output += "];\n\n";
+    output += RUST_DRIVER;
+    output
+}
+
+///
+/// Name a relation as the variant in Rust.
+///
+fn rust_variant(c: Option<char>) -> &'static str {
+    match c {
+        Some('<') => "Relation::Less",
+        Some('=') => "Relation::Equal",
+        Some('>') => "Relation::Greater",
+        _ => "Relation::None",
+    }
+}
+
+///
+/// Emit a matrix of `Relation` in Rust.
+///
+fn rust_matrix<F>(name: &str, height: usize, width: usize, cell: F) -> String
+where
+    F: Fn(usize, usize) -> Option<char>,
+{
+    let mut output = format!("const {}: [[Relation; {}]; {}] = [\n", name, width, height);
+    for i in 0..height {
+        let row: Vec<&str> = (0..width).map(|j| rust_variant(cell(i, j))).collect();
+        output += &format!("    [{}],\n", row.join(", "));
+    }
+    output += "];\n\n";
+    output
+}
+
+///
+/// Emit `TABLE` and `relation()` in Rust.
+///
+fn rust_relation(table: &OpTable, tv: &[String]) -> String {
+    let n = tv.len();
+    let mut output = rust_matrix("TABLE", n, n, |i, j| table.get(&tv[i], &tv[j]));
+    output += "/// The relation between `a` on the left and `b` on the right.\n";
+    output += "pub fn relation(a: Token, b: Token) -> Relation {\n";
+    output += "    TABLE[a as usize][b as usize]\n}\n\n";
+    output
+}
+
+///
+/// The smallest unsigned type in Rust holding `max`.
+///
+fn rust_type(max: usize) -> &'static str {
+    match compress::width(max) {
+        1 => "u8",
+        2 => "u16",
+        _ => "u32",
+    }
+}
+
+///
+/// Emit an array of integers in Rust.
+///
+fn rust_array(name: &str, v: &[usize]) -> String {
+    let list: Vec<String> = v.iter().map(|x| x.to_string()).collect();
+    let ty = rust_type(v.iter().copied().max().unwrap_or(0));
+    format!("const {}: [{}; {}] = [{}];\n", name, ty, v.len(), list.join(", "))
+}
+
+///
+/// Emit `F`, `G` and `relation()` by the precedence functions in Rust.
+///
+fn rust_functions(f: &[usize], g: &[usize]) -> String {
+    let mut output = rust_array("F", f);
+    output += &rust_array("G", g);
+    output += "\n/// The relation between `a` on the left and `b` on the right,\n";
+    output += "/// which is never `Relation::None` by the precedence functions.\n";
+    output += "pub fn relation(a: Token, b: Token) -> Relation {\n";
+    output += "    match F[a as usize].cmp(&G[b as usize]) {\n";
+    output += "        std::cmp::Ordering::Less => Relation::Less,\n";
+    output += "        std::cmp::Ordering::Equal => Relation::Equal,\n";
+    output += "        std::cmp::Ordering::Greater => Relation::Greater,\n";
+    output += "    }\n}\n\n";
+    output
+}
+
+///
+/// Emit the merged matrix and `relation()` in Rust, see `compress::Merged`.
+///
+fn rust_merged(merged: &Merged) -> String {
+    let mut output = String::from("// the merged row and column of every token\n");
+    output += &rust_array("ROWS", &merged.rows);
+    output += &rust_array("COLS", &merged.cols);
+    output += "\n";
+    output += &rust_matrix("MATRIX", merged.height(), merged.width(), |i, j| {
+        compress::relation(merged.matrix[i][j])
+    });
+    output += "/// The relation between `a` on the left and `b` on the right.\n";
+    output += "pub fn relation(a: Token, b: Token) -> Relation {\n";
+    output += "    MATRIX[ROWS[a as usize] as usize][COLS[b as usize] as usize]\n}\n\n";
+    output
+}
+
+///
+/// Emit the comb vector and `relation()` in Rust, see `compress::Packed`.
+///
+fn rust_packed(packed: &Packed) -> String {
+    let mut output = String::from("// the merged row and column of every token\n");
+    output += &rust_array("ROWS", &packed.rows);
+    output += &rust_array("COLS", &packed.cols);
+    output += "// the displacement and the default code of every merged row,\n";
+    output += "// where the code is 0 none, 1 `<`, 2 `=` and 3 `>`\n";
+    output += &rust_array("BASE", &packed.base);
+    output += &rust_array("DEFAULT", &packed.default);
+    output += "// `(row + 1) << 2 | code` of the cells other than the defaults\n";
+    output += &rust_array("SLOTS", &packed.slots);
+    output += "\n/// The relation between `a` on the left and `b` on the right.\n";
+    output += "pub fn relation(a: Token, b: Token) -> Relation {\n";
+    output += "    let row = ROWS[a as usize] as usize;\n";
+    output += "    let slot = SLOTS[BASE[row] as usize + COLS[b as usize] as usize] as usize;\n";
+    output += "    let code = if slot >> 2 == row + 1 { slot & 3 } else { DEFAULT[row] as usize };\n";
+    output += "    match code {\n";
+    output += "        1 => Relation::Less,\n        2 => Relation::Equal,\n        3 => Relation::Greater,\n";
+    output += "        _ => Relation::None,\n    }\n}\n\n";
+    output
+}
+
+const RUST_DRIVER: &str = r#"/// The semantic side of the parsing.
+pub trait Reducer {
+    type Value;
+
+    /// The value of the token at the position.
+    fn shift(&mut self, token: Token, position: usize) -> Result<Self::Value, String>;
+
+    /// The value of the left side of the production.
+    fn reduce(&mut self, production: usize, children: Vec<Self::Value>) -> Result<Self::Value, String>;
+}
+
+struct Entry<V> {
+    symbol: Symbol,
+    value: Option<V>,
+}
+
+fn terminal<V>(entry: &Entry<V>) -> Token {
+    match entry.symbol {
+        Symbol::T(t) => t,
+        Symbol::N(_) => Token::End,
+    }
+}
+
+/// Find the production matching the terminals of the
+/// prime phrase, preferring the exact non-terminals.
+fn find_production<V>(phrase: &[Entry<V>]) -> Option<usize> {
+    let mut first = None;
+    for (k, p) in PRODUCTIONS.iter().enumerate() {
+        if p.right.len() != phrase.len() {
+            continue;
+        }
+        let matched = p.right.iter().zip(phrase.iter()).all(|(r, e)| match (r, e.symbol) {
+            (Symbol::T(a), Symbol::T(b)) => *a == b,
+            (Symbol::N(_), Symbol::N(_)) => true,
+            _ => false,
+        });
+        if !matched {
+            continue;
+        }
+        if p.right.iter().zip(phrase.iter()).all(|(r, e)| *r == e.symbol) {
+            return Some(k);
+        }
+        first.get_or_insert(k);
+    }
+    first
+}
+
+/// Parse the tokens, where the ending `Token::End` is optional.
+pub fn parse<R: Reducer>(tokens: &[Token], reducer: &mut R) -> Result<R::Value, String> {
+    let mut stack: Vec<Entry<R::Value>> = vec![Entry {
+        symbol: Symbol::T(Token::End),
+        value: None,
+    }];
+    let mut ip = 0;
+    loop {
+        let a = tokens.get(ip).copied().unwrap_or(Token::End);
+        // the topmost terminal
+        let mut j = stack.len() - 1;
+        if let Symbol::N(_) = stack[j].symbol {
+            j -= 1;
+        }
+        let top = terminal(&stack[j]);
+        if top == Token::End && a == Token::End {
+            if stack.len() == 2 {
+                return Ok(stack.pop().unwrap().value.unwrap());
+            }
+            return Err("unexpected end of input".to_string());
+        }
+        match relation(top, a) {
+            Relation::Less | Relation::Equal => {
+                let value = reducer.shift(a, ip)?;
+                stack.push(Entry {
+                    symbol: Symbol::T(a),
+                    value: Some(value),
+                });
+                ip += 1;
+            }
+            Relation::Greater => {
+                // find the head of the prime phrase
+                let start = loop {
+                    if j == 0 {
+                        return Err(format!("no prime phrase before {:?} at {}", a, ip));
+                    }
+                    let mut k = j - 1;
+                    if let Symbol::N(_) = stack[k].symbol {
+                        if k == 0 {
+                            return Err(format!("no prime phrase before {:?} at {}", a, ip));
+                        }
+                        k -= 1;
+                    }
+                    if relation(terminal(&stack[k]), terminal(&stack[j])) == Relation::Less {
+                        break k + 1;
+                    }
+                    j = k;
+                };
+                let phrase = stack.split_off(start);
+                let k = match find_production(&phrase) {
+                    Some(k) => k,
+                    None => return Err(format!("no production for the phrase before {:?} at {}", a, ip)),
+                };
+                let children = phrase.into_iter().map(|e| e.value.unwrap()).collect();
+                let value = reducer.reduce(k, children)?;
+                stack.push(Entry {
+                    symbol: Symbol::N(PRODUCTIONS[k].left),
+                    value: Some(value),
+                });
+            }
+            Relation::None => return Err(format!("no relation between {:?} and {:?} at {}", top, a, ip)),
+        }
+    }
+}
+"#;
+
+///
+/// Emit a standalone C parser as a header and a source.
+///
+/// ## Input
+/// `table` the `OpTable` of the grammar.
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `tv` the terminals in order, ended with `$`.
+///
+/// `prefix` the prefix of all the names, like `opg`.
+///
+/// `header` the file name of the header, like `parser.h`.
+///
+/// `layout` the representation of the relation, see `<prefix>_relation()`.
+///
+/// ## Return
+/// The header and the source, with
+/// - `<prefix>_token`: the terminals, where `$` is `<PREFIX>_END`,
+///   and the index is appended to the names of the others which
+///   repeat it, the results or the macros.
+/// - `<prefix>_relation()`: for `Layout::Dense`, the relation in a
+///   matrix of 2 bits per cell, or by the precedence functions if
+///   they exist and `<PREFIX>_USE_FUNCTIONS` is defined, which is
+///   smaller but finds the errors later. For `Layout::Functions`,
+///   only by the functions, or compressed by `OpTable::compress()`
+///   if they do not exist. For `Layout::Merged` and `Layout::Packed`,
+///   in the merged matrix and the comb vector.
+/// - `<prefix>_parse()`: the reentrant driver, which calls back
+///   on every shift and reduction with a user pointer.
+///
+/// ## Example
+/// ```
+/// let (h, c) = codegen::c(&table, &productions, &nts, &tv, "opg", "parser.h", Layout::Dense);
+/// ```
+///
+pub fn c(
+    table: &OpTable,
+    productions: &[Production],
+    nts: &HashSet<String>,
+    tv: &[String],
+    prefix: &str,
+    header: &str,
+    layout: Layout,
+) -> (String, String) {
+    // the tokens share the prefix with the results and the macros
+    let mut reserved: Vec<&str> = C_RESULTS.to_vec();
+    reserved.extend(["TOKEN_COUNT", "PRODUCTION_COUNT", "HAS_FUNCTIONS", "USE_FUNCTIONS"]);
+    let names = unique_names(tv, "END", &reserved, |t| terminal_name(t).to_ascii_uppercase());
+    let upper = prefix.to_ascii_uppercase();
+    let guard: String = header
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    let mut ntv: Vec<&String> = Vec::new();
+    for p in productions {
+        if !ntv.contains(&&p.left) {
+            ntv.push(&p.left);
+        }
+    }
+    let functions = table.gen_functions(tv);
+    let mut comment = String::from("/* Operator precedence parser generated by opg.\n *\n");
+    for line in format_grammar(productions).lines() {
+        comment += &format!(" * {}\n", line.replace("*/", "* /"));
+    }
+    comment += " */\n\n";
+
+    // the header
+    let mut h = comment.clone();
+    h += &format!("#ifndef {}\n#define {}\n\n#include <stddef.h>\n\n", guard, guard);
+    h += "/* the terminals of the grammar */\ntypedef enum {\n";
+    for name in names.iter() {
+        h += &format!("    {}_{},\n", upper, name);
+    }
+    h += &format!("    {}_TOKEN_COUNT\n}} {}_token;\n\n", upper, prefix);
+    h += &format!("#define {}_PRODUCTION_COUNT {}\n", upper, productions.len());
+    if layout == Layout::Dense && functions.is_some() {
+        h += &format!("#define {}_HAS_FUNCTIONS 1\n", upper);
+    }
+    h += "\n/* the results of the parsing */\nenum {\n";
+    let results: Vec<String> = C_RESULTS
+        .iter()
+        .enumerate()
+        .map(|(i, e)| format!("    {}_{} = {}", upper, e, -(i as i32)))
+        .collect();
+    h += &results.join(",\n");
+    h += "\n};\n\n";
+    h += "/* the semantic side of the parsing, with the user pointer */\ntypedef struct {\n";
+    h += &format!("    void *(*shift)(void *user, {}_token token, size_t position);\n", prefix);
+    h += "    void *(*reduce)(void *user, int production, void **children, size_t count);\n";
+    h += &format!("    void *user;\n}} {}_callbacks;\n\n", prefix);
+    h += &format!("extern const char *const {}_token_text[{}_TOKEN_COUNT];\n", prefix, upper);
+    h += &format!("extern const char *const {}_production_text[{}_PRODUCTION_COUNT];\n\n", prefix, upper);
+    h += "/* the relation between a on the left and b on the right: '<', '=', '>' or 0 */\n";
+    h += &format!("char {}_relation({}_token a, {}_token b);\n\n", prefix, prefix, prefix);
+    h += "/* parse the tokens, where the ending END is optional,\n";
+    h += " * and give the value of the starting non-terminal in result,\n";
+    h += " * or the position of the error in error_at if it is not NULL */\n";
+    h += &format!(
+        "int {}_parse(const {}_token *tokens, size_t count, const {}_callbacks *callbacks,\n    void **result, size_t *error_at);\n\n",
+        prefix, prefix, prefix
+    );
+    h += &format!("#endif /* {} */\n", guard);
+
+    // the source
+    let mut s = comment;
+    s += &format!("#include <stdlib.h>\n\n#include \"{}\"\n\n", header);
+    let texts: Vec<String> = tv.iter().map(|t| c_string(t)).collect();
+    s += &format!(
+        "const char *const {}_token_text[{}_TOKEN_COUNT] = {{{}}};\n\n",
+        prefix,
+        upper,
+        texts.join(", ")
+    );
+    let texts: Vec<String> = productions.iter().map(|p| format!("    {}", c_string(&p.to_string()))).collect();
+    s += &format!(
+        "const char *const {}_production_text[{}_PRODUCTION_COUNT] = {{\n{}\n}};\n\n",
+        prefix,
+        upper,
+        texts.join(",\n")
+    );
+    s += &match layout {
+        Layout::Dense => c_relation(table, tv, prefix, &functions),
+        Layout::Merged => c_merged(&compress::merge(table, tv), prefix),
+        Layout::Packed => c_packed(&compress::pack(table, tv), prefix),
+        Layout::Functions => match table.compress(tv) {
+            Compressed::Functions(f, g) => c_functions(&f, &g, prefix),
+            Compressed::Merged(merged) => c_merged(&merged, prefix),
+            Compressed::Packed(packed) => c_packed(&packed, prefix),
+        },
+    };
+
+    s += "/* the right sides, where a non-terminal is -1 - index */\n";
+    let mut rhs: Vec<String> = Vec::new();
+    let mut spans: Vec<String> = Vec::new();
+    for p in productions {
+        let left = ntv.iter().position(|v| **v == p.left).unwrap();
+        spans.push(format!("    {{{}, {}, {}}}", left, rhs.len(), p.right.len()));
+        for v in p.right.iter() {
+            rhs.push(match tv.iter().position(|t| t == v) {
+                Some(i) if !nts.contains(v) => format!("{}_{}", upper, names[i]),
+                _ => format!("{}", -1 - ntv.iter().position(|n| *n == v).unwrap_or(0) as i64),
+            });
+        }
+    }
+    if rhs.is_empty() {
+        rhs.push("0".to_string());
+    }
+    s += &format!("static const int {}_rhs[] = {{{}}};\n\n", prefix, rhs.join(", "));
+    s += "/* the left side, the offset in the right sides and the length */\n";
+    s += &format!(
+        "static const int {}_productions[{}_PRODUCTION_COUNT][3] = {{\n{}\n}};\n\n",
+        prefix,
+        upper,
+        spans.join(",\n")
+    );
+    s += &C_DRIVER.replace("opg_", &format!("{}_", prefix)).replace("OPG_", &format!("{}_", upper));
+    (h, s)
+}
+
+///
+/// Quote a string as a C string literal.
+///
+fn c_string(v: &str) -> String {
+    let mut s = String::from("\"");
+    for c in v.chars() {
+        match c {
+            '"' | '\\' => {
+                s.push('\\');
+                s.push(c);
+            }
+            c if c.is_ascii() && !c.is_ascii_control() => s.push(c),
+            c => {
+                let mut buf = [0u8; 4];
+                for b in c.encode_utf8(&mut buf).bytes() {
+                    s += &format!("\\{:03o}", b);
+                }
+            }
+        }
+    }
+    s.push('"');
+    s
+}
+
+///
+/// Emit the relation matrix, the precedence functions
+/// and `<prefix>_relation()` in C.
+///
+/// ## Principles
+/// Every cell takes 2 bits, 0 for no relation, 1 for `<`,
+/// 2 for `=` and 3 for `>`. The cell `(a, b)` is the
+/// `a * n + b`-th one, and 4 cells are packed in a byte
+/// from the lowest bits.
+///
+fn c_relation(table: &OpTable, tv: &[String], prefix: &str, functions: &Option<(Vec<usize>, Vec<usize>)>) -> String {
+    let upper = prefix.to_ascii_uppercase();
+    let n = tv.len();
+    let mut output = String::new();
+    if let Some((f, g)) = functions {
+        output += &format!("#ifdef {}_USE_FUNCTIONS\n\n", upper);
+        output += &c_functions(f, g, prefix);
+        output += "#else\n\n";
+    }
+    output += &c_bits(&format!("{}_matrix", prefix), n, n, |i, j| table.get(&tv[i], &tv[j]));
+    output += &format!("char {}_relation({}_token a, {}_token b)\n{{\n", prefix, prefix, prefix);
+    output += &format!("    size_t k = (size_t)a * {}_TOKEN_COUNT + (size_t)b;\n", upper);
+    output += &format!("    return \"\\0<=>\"[({}_matrix[k / 4] >> (k % 4 * 2)) & 3];\n}}\n\n", prefix);
+    if functions.is_some() {
+        output += &format!("#endif /* {}_USE_FUNCTIONS */\n\n", upper);
+    }
+    output
+}
+
+///
+/// Emit a matrix of 2 bits per cell in C,
+/// packed as in `c_relation()`.
+///
+fn c_bits<F>(name: &str, height: usize, width: usize, cell: F) -> String
+where
+    F: Fn(usize, usize) -> Option<char>,
+{
+    let mut bytes: Vec<u8> = vec![0; (height * width).div_ceil(4)];
+    for i in 0..height {
+        for j in 0..width {
+            let bits = match cell(i, j) {
+                Some('<') => 1,
+                Some('=') => 2,
+                Some('>') => 3,
+                _ => 0,
+            };
+            let k = i * width + j;
+            bytes[k / 4] |= bits << ((k % 4) * 2);
+        }
+    }
+    let hex: Vec<String> = bytes.iter().map(|b| format!("0x{:02x}", b)).collect();
+    let rows: Vec<String> = hex.chunks(12).map(|row| format!("    {}", row.join(", "))).collect();
+    let mut output = String::from("/* 2 bits for every cell: 0 none, 1 '<', 2 '=' and 3 '>' */\n");
+    output += &format!("static const unsigned char {}[{}] = {{\n{}\n}};\n\n", name, bytes.len(), rows.join(",\n"));
+    output
+}
+
+///
+/// Emit a static array of integers in C.
+///
+fn c_array(name: &str, v: &[usize]) -> String {
+    let ty = match compress::width(v.iter().copied().max().unwrap_or(0)) {
+        1 => "unsigned char",
+        2 => "unsigned short",
+        _ => "unsigned int",
+    };
+    let list: Vec<String> = v.iter().map(|x| x.to_string()).collect();
+    let rows: Vec<String> = list.chunks(16).map(|row| format!("    {}", row.join(", "))).collect();
+    format!("static const {} {}[{}] = {{\n{}\n}};\n", ty, name, v.len(), rows.join(",\n"))
+}
+
+///
+/// Emit the precedence functions and `<prefix>_relation()` in C.
+///
+fn c_functions(f: &[usize], g: &[usize], prefix: &str) -> String {
+    let mut output = c_array(&format!("{}_f", prefix), f);
+    output += &c_array(&format!("{}_g", prefix), g);
+    output += &format!("\nchar {}_relation({}_token a, {}_token b)\n{{\n", prefix, prefix, prefix);
+    output += &format!("    if ({}_f[a] < {}_g[b])\n        return '<';\n", prefix, prefix);
+    output += &format!("    if ({}_f[a] == {}_g[b])\n        return '=';\n", prefix, prefix);
+    output += "    return '>';\n}\n\n";
+    output
+}
+
+///
+/// Emit the merged matrix and `<prefix>_relation()` in C,
+/// see `compress::Merged`.
+///
+fn c_merged(merged: &Merged, prefix: &str) -> String {
+    let mut output = String::from("/* the merged row and column of every token */\n");
+    output += &c_array(&format!("{}_rows", prefix), &merged.rows);
+    output += &c_array(&format!("{}_cols", prefix), &merged.cols);
+    output += "\n";
+    output += &c_bits(&format!("{}_matrix", prefix), merged.height(), merged.width(), |i, j| {
+        compress::relation(merged.matrix[i][j])
+    });
+    output += &format!("char {}_relation({}_token a, {}_token b)\n{{\n", prefix, prefix, prefix);
+    output += &format!(
+        "    size_t k = (size_t){}_rows[a] * {} + (size_t){}_cols[b];\n",
+        prefix,
+        merged.width(),
+        prefix
+    );
+    output += &format!("    return \"\\0<=>\"[({}_matrix[k / 4] >> (k % 4 * 2)) & 3];\n}}\n\n", prefix);
+    output
+}
+
+///
+/// Emit the comb vector and `<prefix>_relation()` in C,
+/// see `compress::Packed`.
+///
+fn c_packed(packed: &Packed, prefix: &str) -> String {
+    let mut output = String::from("/* the merged row and column of every token */\n");
+    output += &c_array(&format!("{}_rows", prefix), &packed.rows);
+    output += &c_array(&format!("{}_cols", prefix), &packed.cols);
+    output += "/* the displacement and the default code of every merged row,\n";
+    output += " * where the code is 0 none, 1 '<', 2 '=' and 3 '>' */\n";
+    output += &c_array(&format!("{}_base", prefix), &packed.base);
+    output += &c_array(&format!("{}_default", prefix), &packed.default);
+    output += "/* (row + 1) << 2 | code of the cells other than the defaults */\n";
+    output += &c_array(&format!("{}_slots", prefix), &packed.slots);
+    output += &format!("\nchar {}_relation({}_token a, {}_token b)\n{{\n", prefix, prefix, prefix);
+    output += &format!("    size_t row = {}_rows[a];\n", prefix);
+    output += &format!("    size_t slot = {}_slots[{}_base[row] + {}_cols[b]];\n", prefix, prefix, prefix);
+    output += &format!("    size_t code = slot >> 2 == row + 1 ? slot & 3 : {}_default[row];\n", prefix);
+    output += "    return \"\\0<=>\"[code];\n}\n\n";
+    output
+}
+
+const C_DRIVER: &str = r#"/* find the production matching the terminals of the
+ * prime phrase, preferring the exact non-terminals */
+static int opg_find_production(const int *phrase, size_t length)
+{
+    int first = -1;
+    int k;
+    for (k = 0; k < OPG_PRODUCTION_COUNT; k++) {
+        const int *right = opg_rhs + opg_productions[k][1];
+        int matched = 1, exact = 1;
+        size_t i;
+        if ((size_t)opg_productions[k][2] != length)
+            continue;
+        for (i = 0; i < length && matched; i++) {
+            if ((right[i] >= 0) != (phrase[i] >= 0) || (right[i] >= 0 && right[i] != phrase[i]))
+                matched = 0;
+            else if (right[i] != phrase[i])
+                exact = 0;
+        }
+        if (!matched)
+            continue;
+        if (exact)
+            return k;
+        if (first < 0)
+            first = k;
+    }
+    return first;
+}
+
+int opg_parse(const opg_token *tokens, size_t count, const opg_callbacks *callbacks,
+    void **result, size_t *error_at)
+{
+    /* the stack of the symbols, where a non-terminal is -1 - index,
+     * and their values */
+    size_t capacity = 16, top = 1, ip = 0;
+    int *symbols = malloc(capacity * sizeof *symbols);
+    void **values = malloc(capacity * sizeof *values);
+    int status = OPG_OK;
+    if (!symbols || !values) {
+        free(symbols);
+        free(values);
+        return OPG_ERR_MEMORY;
+    }
+    symbols[0] = OPG_END;
+    values[0] = NULL;
+    for (;;) {
+        opg_token a = ip < count ? tokens[ip] : OPG_END;
+        size_t j = top - 1;
+        char relation;
+        /* the topmost terminal */
+        if (symbols[j] < 0)
+            j--;
+        if (symbols[j] == OPG_END && a == OPG_END) {
+            if (top == 2)
+                *result = values[1];
+            else
+                status = OPG_ERR_END;
+            break;
+        }
+        relation = opg_relation((opg_token)symbols[j], a);
+        if (relation == '<' || relation == '=') {
+            if (top == capacity) {
+                int *s;
+                void **v;
+                capacity *= 2;
+                s = realloc(symbols, capacity * sizeof *symbols);
+                if (s)
+                    symbols = s;
+                v = realloc(values, capacity * sizeof *values);
+                if (v)
+                    values = v;
+                if (!s || !v) {
+                    status = OPG_ERR_MEMORY;
+                    break;
+                }
+            }
+            symbols[top] = a;
+            values[top] = callbacks->shift(callbacks->user, a, ip);
+            top++;
+            ip++;
+        } else if (relation == '>') {
+            /* find the head of the prime phrase */
+            size_t start = 0, k;
+            int p;
+            while (start == 0) {
+                if (j == 0)
+                    break;
+                k = j - 1;
+                if (symbols[k] < 0) {
+                    if (k == 0)
+                        break;
+                    k--;
+                }
+                if (opg_relation((opg_token)symbols[k], (opg_token)symbols[j]) == '<')
+                    start = k + 1;
+                j = k;
+            }
+            if (start == 0) {
+                status = OPG_ERR_PHRASE;
+                break;
+            }
+            p = opg_find_production(symbols + start, top - start);
+            if (p < 0) {
+                status = OPG_ERR_PRODUCTION;
+                break;
+            }
+            values[start] = callbacks->reduce(callbacks->user, p, values + start, top - start);
+            symbols[start] = -1 - opg_productions[p][0];
+            top = start + 1;
+        } else {
+            status = OPG_ERR_RELATION;
+            break;
+        }
+    }
+    if (status != OPG_OK && error_at)
+        *error_at = ip;
+    free(symbols);
+    free(values);
+    return status;
+}
+"#;
@@ -0,0 +1,459 @@
+//! # compress
+//!
+//! `compress` represents an `OpTable` in less space than
+//! the dense matrix of `|T|×|T|` cells, by the precedence
+//! functions, or by merging the same rows and columns and
+//! packing the rest in a comb vector.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::table::{OpTable, Relations};
+
+///
+/// The representation of the relations in the generated code.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layout {
+    /// the matrix of all the cells.
+    Dense,
+    /// the precedence functions `f` and `g`, which
+    /// find the errors later as they have no blank.
+    Functions,
+    /// the matrix of the merged rows and columns, see `Merged`.
+    Merged,
+    /// the merged rows and columns in a comb vector, see `Packed`.
+    Packed,
+}
+
+fn code(c: Option<char>) -> usize {
+    match c {
+        Some('<') => 1,
+        Some('=') => 2,
+        Some('>') => 3,
+        _ => 0,
+    }
+}
+
+///
+/// The relation of a code, see `Merged`.
+///
+pub fn relation(code: usize) -> Option<char> {
+    match code {
+        1 => Some('<'),
+        2 => Some('='),
+        3 => Some('>'),
+        _ => None,
+    }
+}
+
+///
+/// The bytes of the smallest unsigned integer holding `max`.
+///
+pub fn width(max: usize) -> usize {
+    if max <= u8::MAX as usize {
+        1
+    } else if max <= u16::MAX as usize {
+        2
+    } else {
+        4
+    }
+}
+
+///
+/// The bytes of an array of integers, see `width()`.
+///
+fn array_size(v: &[usize]) -> usize {
+    v.len() * width(v.iter().copied().max().unwrap_or(0))
+}
+
+///
+/// The table with the same rows and columns merged.
+///
+/// The relation between the terminals `i` and `j` in the
+/// order is the code `matrix[rows[i]][cols[j]]`, which is
+/// 0 for no relation, and 1, 2, 3 for `<`, `=`, `>`.
+///
+#[derive(Clone, Debug)]
+pub struct Merged {
+    /// the terminals in order.
+    pub order: Vec<String>,
+    /// the merged row of every terminal on the left.
+    pub rows: Vec<usize>,
+    /// the merged column of every terminal on the right.
+    pub cols: Vec<usize>,
+    /// the codes of the merged rows and columns.
+    pub matrix: Vec<Vec<usize>>,
+}
+
+///
+/// The table with the same rows and columns merged,
+/// and the rows packed in a comb vector.
+///
+/// The codes are 0 for no relation, and 1, 2, 3 for `<`, `=`, `>`.
+/// The relation between the terminals `i` and `j` in the order
+/// is looked up by `r = rows[i]` and `slot = slots[base[r] + cols[j]]`,
+/// which is `(r + 1) << 2 | code` if the cell is kept for the row `r`,
+/// or else the code is `default[r]`. A slot of no row is 0.
+///
+#[derive(Clone, Debug)]
+pub struct Packed {
+    /// the terminals in order.
+    pub order: Vec<String>,
+    /// the merged row of every terminal on the left.
+    pub rows: Vec<usize>,
+    /// the merged column of every terminal on the right.
+    pub cols: Vec<usize>,
+    /// the displacement of every merged row.
+    pub base: Vec<usize>,
+    /// the most common code of every merged row,
+    /// whose cells are not kept in the comb vector.
+    pub default: Vec<usize>,
+    /// the comb vector, long enough for `base[r] + cols[j]`.
+    pub slots: Vec<usize>,
+}
+
+///
+/// Number the distinct vectors in the order of appearance.
+///
+/// ## Return
+/// The number of every vector, and the distinct ones.
+///
+fn number(vectors: Vec<Vec<usize>>) -> (Vec<usize>, Vec<Vec<usize>>) {
+    let mut numbers: HashMap<Vec<usize>, usize> = HashMap::new();
+    let mut distinct: Vec<Vec<usize>> = Vec::new();
+    let mut index: Vec<usize> = Vec::new();
+    for v in vectors {
+        let k = *numbers.entry(v.clone()).or_insert_with(|| {
+            distinct.push(v);
+            distinct.len() - 1
+        });
+        index.push(k);
+    }
+    (index, distinct)
+}
+
+///
+/// Merge the same rows and columns of the table.
+///
+/// ## Input
+/// `table` the `OpTable` of the grammar.
+///
+/// `order` the terminals in order.
+///
+/// ## Example
+/// ```
+/// let merged = compress::merge(&table, &tv);
+/// assert_eq!(merged.get("+", "*"), table.get("+", "*"));
+/// ```
+///
+/// ## Principles
+/// The terminals with the same column are merged, then
+/// the ones with the same row in the merged columns,
+/// both numbered in the order of appearance.
+///
+pub fn merge(table: &OpTable, order: &[String]) -> Merged {
+    let cell = |i: usize, j: usize| code(table.get(&order[i], &order[j]));
+    let n = order.len();
+    let (cols, distinct) = number((0..n).map(|j| (0..n).map(|i| cell(i, j)).collect()).collect());
+    // the rows of the merged columns
+    let (rows, matrix) = number((0..n).map(|i| distinct.iter().map(|col| col[i]).collect()).collect());
+    Merged {
+        order: order.to_vec(),
+        rows,
+        cols,
+        matrix,
+    }
+}
+
+impl Merged {
+    ///
+    /// Query the relation between the terminals
+    /// `i` on the left and `j` on the right in the order.
+    ///
+    pub fn relation(&self, i: usize, j: usize) -> Option<char> {
+        relation(self.matrix[self.rows[i]][self.cols[j]])
+    }
+
+    ///
+    /// Query the relation between
+    /// terminal `a` on the left and
+    /// terminal `b` on the right.
+    ///
+    /// ## Return
+    /// One of `<`, `=`, `>` or `None`
+    /// if the pair has no relation.
+    ///
+    pub fn get(&self, a: &str, b: &str) -> Option<char> {
+        let i = self.order.iter().position(|t| t == a)?;
+        let j = self.order.iter().position(|t| t == b)?;
+        self.relation(i, j)
+    }
+
+    ///
+    /// The number of the merged rows.
+    ///
+    pub fn height(&self) -> usize {
+        self.matrix.len()
+    }
+
+    ///
+    /// The number of the merged columns.
+    ///
+    pub fn width(&self) -> usize {
+        self.matrix.first().map_or(0, |row| row.len())
+    }
+
+    ///
+    /// The bytes of the maps and the matrix
+    /// of 2 bits per cell, see `Report`.
+    ///
+    pub fn size(&self) -> usize {
+        array_size(&self.rows) + array_size(&self.cols) + (self.height() * self.width()).div_ceil(4)
+    }
+}
+
+impl Relations for Merged {
+    fn get(&self, a: &str, b: &str) -> Option<char> {
+        Merged::get(self, a, b)
+    }
+
+    fn contains(&self, t: &str) -> bool {
+        self.order.iter().any(|v| v == t)
+    }
+}
+
+///
+/// Pack the table by merging and the comb vector.
+///
+/// ## Input
+/// `table` the `OpTable` of the grammar.
+///
+/// `order` the terminals in order.
+///
+/// ## Example
+/// ```
+/// let packed = compress::pack(&table, &tv);
+/// assert_eq!(packed.get("+", "*"), table.get("+", "*"));
+/// ```
+///
+/// ## Principles
+/// 1. The rows and the columns are merged by `merge()`.
+/// 2. The most common code of a merged row is its default,
+///    and only the other cells are kept, as most of the cells
+///    of an operator precedence table are not blank.
+/// 3. The merged rows are placed from the one with the most
+///    kept cells, each at the first displacement where its
+///    cells fall in the free slots. A row could fill the
+///    holes of the others, since a slot records its row.
+/// 4. The vector is padded, so that `base[r] + cols[j]`
+///    is always in it.
+///
+pub fn pack(table: &OpTable, order: &[String]) -> Packed {
+    let Merged {
+        order,
+        rows,
+        cols,
+        matrix,
+    } = merge(table, order);
+    let width = matrix.first().map_or(0, |row| row.len());
+    // the most common code, or the smaller one if tied
+    let default: Vec<usize> = matrix
+        .iter()
+        .map(|row| (0..4).max_by_key(|code| (row.iter().filter(|c| *c == code).count(), 4 - code)).unwrap())
+        .collect();
+    let kept = |r: usize| -> Vec<usize> { (0..width).filter(|c| matrix[r][*c] != default[r]).collect() };
+    let mut byrank: Vec<usize> = (0..matrix.len()).collect();
+    byrank.sort_by_key(|r| std::cmp::Reverse(kept(*r).len()));
+    let mut base = vec![0; matrix.len()];
+    let mut slots: Vec<usize> = Vec::new();
+    for r in byrank {
+        let cells = kept(r);
+        if cells.is_empty() {
+            continue;
+        }
+        let d = (0..)
+            .find(|d| cells.iter().all(|c| slots.get(d + c).is_none_or(|s| *s == 0)))
+            .unwrap();
+        for c in cells {
+            if slots.len() <= d + c {
+                slots.resize(d + c + 1, 0);
+            }
+            slots[d + c] = (r + 1) << 2 | matrix[r][c];
+        }
+        base[r] = d;
+    }
+    let end = base.iter().max().map_or(0, |d| d + width);
+    slots.resize(slots.len().max(end), 0);
+    Packed {
+        order,
+        rows,
+        cols,
+        base,
+        default,
+        slots,
+    }
+}
+
+impl Packed {
+    ///
+    /// Query the relation between the terminals
+    /// `i` on the left and `j` on the right in the order.
+    ///
+    pub fn relation(&self, i: usize, j: usize) -> Option<char> {
+        let r = self.rows[i];
+        let slot = self.slots[self.base[r] + self.cols[j]];
+        if slot >> 2 == r + 1 {
+            relation(slot & 3)
+        } else {
+            relation(self.default[r])
+        }
+    }
+
+    ///
+    /// Query the relation between
+    /// terminal `a` on the left and
+    /// terminal `b` on the right.
+    ///
+    /// ## Return
+    /// One of `<`, `=`, `>` or `None`
+    /// if the pair has no relation.
+    ///
+    pub fn get(&self, a: &str, b: &str) -> Option<char> {
+        let i = self.order.iter().position(|t| t == a)?;
+        let j = self.order.iter().position(|t| t == b)?;
+        self.relation(i, j)
+    }
+
+    ///
+    /// The number of the merged rows.
+    ///
+    pub fn height(&self) -> usize {
+        self.base.len()
+    }
+
+    ///
+    /// The number of the merged columns.
+    ///
+    pub fn width(&self) -> usize {
+        self.cols.iter().max().map_or(0, |c| c + 1)
+    }
+
+    ///
+    /// The bytes of the maps, the displacements,
+    /// the defaults and the comb vector, see `Report`.
+    ///
+    pub fn size(&self) -> usize {
+        [&self.rows, &self.cols, &self.base, &self.default, &self.slots].iter().map(|v| array_size(v)).sum()
+    }
+}
+
+impl Relations for Packed {
+    fn get(&self, a: &str, b: &str) -> Option<char> {
+        Packed::get(self, a, b)
+    }
+
+    fn contains(&self, t: &str) -> bool {
+        self.order.iter().any(|v| v == t)
+    }
+}
+
+///
+/// A compressed table from `OpTable::compress()`.
+///
+#[derive(Clone, Debug)]
+pub enum Compressed {
+    /// the precedence functions `f` and `g` in the order.
+    Functions(Vec<usize>, Vec<usize>),
+    Merged(Merged),
+    Packed(Packed),
+}
+
+impl Compressed {
+    ///
+    /// The layout of the generated code for it.
+    ///
+    pub fn layout(&self) -> Layout {
+        match self {
+            Compressed::Functions(_, _) => Layout::Functions,
+            Compressed::Merged(_) => Layout::Merged,
+            Compressed::Packed(_) => Layout::Packed,
+        }
+    }
+}
+
+///
+/// The sizes of the representations in bytes.
+///
+/// Every cell of a matrix takes 2 bits as in
+/// `codegen::c()`, and every integer takes the
+/// bytes of the smallest unsigned type holding
+/// the largest one in its array.
+///
+#[derive(Clone, Debug)]
+pub struct Report {
+    /// the number of the terminals.
+    pub terminals: usize,
+    /// the numbers of the merged rows and columns.
+    pub shape: (usize, usize),
+    /// the length of the comb vector.
+    pub slots: usize,
+    pub dense: usize,
+    /// `None` if the functions do not exist.
+    pub functions: Option<usize>,
+    /// see `Merged::size()`.
+    pub merged: usize,
+    /// see `Packed::size()`.
+    pub packed: usize,
+}
+
+///
+/// Measure the representations of the table.
+///
+/// ## Example
+/// ```
+/// println!("{}", compress::report(&table, &tv));
+/// ```
+///
+pub fn report(table: &OpTable, order: &[String]) -> Report {
+    let n = order.len();
+    let merged = merge(table, order);
+    let packed = pack(table, order);
+    Report {
+        terminals: n,
+        shape: (merged.height(), merged.width()),
+        slots: packed.slots.len(),
+        dense: (n * n).div_ceil(4),
+        functions: table.gen_functions(order).map(|(f, g)| array_size(&f) + array_size(&g)),
+        merged: merged.size(),
+        packed: packed.size(),
+    }
+}
+
+impl Display for Report {
+    ///
+    /// Output the sizes with the ratios to the dense matrix.
+    ///
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let ratio = |size: usize| {
+            if self.dense == 0 {
+                String::from("-")
+            } else {
+                format!("{:.1}%", size as f64 * 100.0 / self.dense as f64)
+            }
+        };
+        writeln!(
+            f,
+            "{} terminals, {} x {} after merging, {} slots in the comb vector.",
+            self.terminals, self.shape.0, self.shape.1, self.slots
+        )?;
+        writeln!(f, " \tbytes\tratio\t")?;
+        writeln!(f, "dense\t{}\t{}\t", self.dense, ratio(self.dense))?;
+        match self.functions {
+            Some(size) => writeln!(f, "functions\t{}\t{}\t", size, ratio(size))?,
+            None => writeln!(f, "functions\t-\t-\t")?,
+        }
+        writeln!(f, "merged\t{}\t{}\t", self.merged, ratio(self.merged))?;
+        writeln!(f, "packed\t{}\t{}\t", self.packed, ratio(self.packed))
+    }
+}
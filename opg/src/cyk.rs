@@ -0,0 +1,90 @@
+//! # cyk
+//!
+//! `cyk` provides the CYK recognizer
+//! over a grammar in the Chomsky normal form
+//! given by `cnf::to_cnf()`.
+
+use std::collections::HashSet;
+
+use crate::Production;
+
+///
+/// Fill the CYK table.
+///
+/// ## Input
+/// `productions` the grammar in the Chomsky normal form.
+///
+/// `tokens` the input, without `$`.
+///
+/// ## Return
+/// `table[l][i]` is the set of non-terminals deriving
+/// the `l + 1` tokens from position `i`.
+///
+/// ## Principles
+/// - `A -> a`: A is in `table[0][i]` if the i-th token is `a`.
+/// - `A -> B C`: A is in `table[l][i]` if B derives the first
+///   `k` tokens and C derives the rest for some `k`.
+///
+pub fn gen_cyk_table(productions: &[Production], tokens: &[String]) -> Vec<Vec<HashSet<String>>> {
+    let n = tokens.len();
+    let mut table: Vec<Vec<HashSet<String>>> = (0..n).map(|l| vec![HashSet::new(); n - l]).collect();
+    for (i, t) in tokens.iter().enumerate() {
+        for p in productions.iter().filter(|p| p.right.len() == 1 && p.right[0] == *t) {
+            table[0][i].insert(p.left.clone());
+        }
+    }
+    for l in 1..n {
+        for i in 0..n - l {
+            for k in 0..l {
+                // the first k + 1 tokens and the rest l - k tokens
+                for p in productions.iter().filter(|p| p.right.len() == 2) {
+                    if table[k][i].contains(&p.right[0]) && table[l - k - 1][i + k + 1].contains(&p.right[1]) {
+                        table[l][i].insert(p.left.clone());
+                    }
+                }
+            }
+        }
+    }
+    table
+}
+
+///
+/// Decide whether the grammar accepts the tokens.
+///
+/// The empty input is accepted if there is `S0 -> ε`.
+/// Nothing is accepted by the empty grammar, which is
+/// left by `cnf::to_cnf()` if the start symbol is unproductive.
+///
+pub fn accepts(productions: &[Production], table: &[Vec<HashSet<String>>], tokens: &[String]) -> bool {
+    let start = match productions.first() {
+        Some(p) => &p.left,
+        None => return false,
+    };
+    if tokens.is_empty() {
+        return productions.iter().any(|p| p.left == *start && p.right.is_empty());
+    }
+    table[tokens.len() - 1][0].contains(start)
+}
+
+///
+/// Format the triangular table, with the
+/// longest span on the top and the tokens at the bottom.
+///
+pub fn format_cyk_table(table: &[Vec<HashSet<String>>], tokens: &[String]) -> String {
+    let mut output = String::new();
+    for (l, row) in table.iter().enumerate().rev() {
+        output += &format!("{}", l + 1);
+        for cell in row {
+            let mut items: Vec<&String> = cell.iter().collect();
+            items.sort();
+            let items: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
+            output += &format!("\t{{{}}}", items.join(","));
+        }
+        output += "\n";
+    }
+    for t in tokens {
+        output += &format!("\t{}", t);
+    }
+    output += "\n";
+    output
+}
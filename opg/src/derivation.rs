@@ -0,0 +1,222 @@
+//! # derivation
+//!
+//! `derivation` takes a parse tree out of the
+//! shared packed parse forest, and prints its
+//! leftmost and rightmost derivations.
+
+use std::collections::HashSet;
+
+use crate::earley::{Forest, Node};
+use crate::Production;
+
+///
+/// A parse tree, where a leaf is a terminal
+/// and an inner node is a non-terminal with
+/// the production applied.
+///
+pub enum Tree {
+    Leaf(String),
+    Inner(usize, Vec<Tree>),
+}
+
+///
+/// Take a parse tree out of the forest.
+///
+/// ## Return
+/// The tree of the first packed alternatives,
+/// or `None` if every alternative is in a cycle.
+///
+/// ## Principles
+/// Try the packed alternatives of every node in order,
+/// skipping the ones reaching a node on the path from
+/// the root, which only give the cycles like `A => A`.
+///
+pub fn extract_tree(forest: &Forest) -> Option<Tree> {
+    let mut path: HashSet<Node> = HashSet::new();
+    extract_node(forest, &forest.root, &mut path)
+}
+
+fn extract_node(forest: &Forest, node: &Node, path: &mut HashSet<Node>) -> Option<Tree> {
+    if let Node::Terminal(t, _) = node {
+        return Some(Tree::Leaf(t.clone()));
+    }
+    if !path.insert(node.clone()) {
+        return None;
+    }
+    let mut tree: Option<Tree> = None;
+    for (k, children) in forest.packed.get(node).into_iter().flatten() {
+        let subtrees: Option<Vec<Tree>> = children.iter().map(|c| extract_node(forest, c, path)).collect();
+        if let Some(subtrees) = subtrees {
+            tree = Some(Tree::Inner(*k, subtrees));
+            break;
+        }
+    }
+    path.remove(node);
+    tree
+}
+
+///
+/// A step of the derivation, i.e. the production
+/// applied and the sentential form after it.
+///
+pub struct Step {
+    pub prod: usize,
+    pub form: Vec<String>,
+}
+
+///
+/// A symbol in the sentential form, which is
+/// a terminal or a subtree to be expanded.
+///
+enum Slot<'a> {
+    Done(String),
+    Todo(&'a Tree),
+}
+
+///
+/// Generate the derivation of the tree.
+///
+/// ## Input
+/// `tree` the parse tree.
+///
+/// `productions` the vector of struct `Production`
+///
+/// `rightmost` whether to expand the rightmost non-terminal
+/// first, otherwise the leftmost one.
+///
+/// ## Return
+/// The starting non-terminal, and the steps.
+///
+/// ## Example
+/// ```
+/// let tree = derivation::extract_tree(&forest).unwrap();
+/// let (start, steps) = derivation::derive(&tree, &productions, false);
+/// ```
+///
+pub fn derive(tree: &Tree, productions: &[Production], rightmost: bool) -> (String, Vec<Step>) {
+    let symbol = |slot: &Slot| match slot {
+        Slot::Done(t) => t.clone(),
+        Slot::Todo(Tree::Leaf(t)) => t.clone(),
+        Slot::Todo(Tree::Inner(k, _)) => productions[*k].left.clone(),
+    };
+    let mut form: Vec<Slot> = vec![Slot::Todo(tree)];
+    let start = symbol(&form[0]);
+    let mut steps: Vec<Step> = Vec::new();
+    loop {
+        let mut todo = (0..form.len()).filter(|i| matches!(form[*i], Slot::Todo(Tree::Inner(..))));
+        let i = match if rightmost { todo.next_back() } else { todo.next() } {
+            Some(i) => i,
+            None => break,
+        };
+        let (k, children) = match form[i] {
+            Slot::Todo(Tree::Inner(k, children)) => (*k, children),
+            _ => unreachable!(),
+        };
+        let slots = children.iter().map(|c| match c {
+            Tree::Leaf(t) => Slot::Done(t.clone()),
+            inner => Slot::Todo(inner),
+        });
+        form.splice(i..i + 1, slots);
+        steps.push(Step {
+            prod: k,
+            form: form.iter().map(symbol).collect(),
+        });
+    }
+    (start, steps)
+}
+
+fn join(form: &[String]) -> String {
+    if form.is_empty() {
+        return crate::EPSILON.to_string();
+    }
+    form.join(" ")
+}
+
+///
+/// Output the derivation as plain text,
+/// one step in a line with the production.
+///
+pub fn format_derivation(start: &str, steps: &[Step], productions: &[Production]) -> String {
+    let mut output = format!("{}\n", start);
+    for step in steps {
+        output += &format!("=> {}\t({})\n", join(&step.form), productions[step.prod]);
+    }
+    output
+}
+
+///
+/// Escape a symbol for the math mode of LaTeX.
+///
+fn latex_symbol(v: &str) -> String {
+    if v == crate::EPSILON {
+        return "\\varepsilon".to_string();
+    }
+    let mut s = String::new();
+    for c in v.chars() {
+        match c {
+            '{' | '}' | '_' | '#' | '%' | '&' | '$' => s += &format!("\\{}", c),
+            '\\' => s += "\\backslash{}",
+            '^' => s += "\\hat{}",
+            '~' => s += "\\sim{}",
+            _ => s.push(c),
+        }
+    }
+    if v.chars().count() > 1 && v.chars().all(char::is_alphanumeric) {
+        return format!("\\mathrm{{{}}}", s);
+    }
+    s
+}
+
+fn latex_form(form: &[String]) -> String {
+    if form.is_empty() {
+        return latex_symbol(crate::EPSILON);
+    }
+    let symbols: Vec<String> = form.iter().map(|v| latex_symbol(v)).collect();
+    symbols.join("\\,")
+}
+
+///
+/// Output the derivation in the LaTeX `align*`
+/// environment, with the production in every step.
+///
+pub fn format_latex(start: &str, steps: &[Step], productions: &[Production]) -> String {
+    let mut output = String::from("\\begin{align*}\n");
+    for (i, step) in steps.iter().enumerate() {
+        let p = &productions[step.prod];
+        let left = if i == 0 { latex_symbol(start) } else { String::new() };
+        output += &format!(
+            "{} &\\Rightarrow {} && ({} \\to {})",
+            left,
+            latex_form(&step.form),
+            latex_symbol(&p.left),
+            latex_form(&p.right)
+        );
+        output += if i + 1 < steps.len() { " \\\\\n" } else { "\n" };
+    }
+    output += "\\end{align*}\n";
+    output
+}
+
+///
+/// Output the parse tree with indents.
+///
+pub fn format_tree(tree: &Tree, productions: &[Production]) -> String {
+    let mut output = String::new();
+    format_subtree(tree, productions, 0, &mut output);
+    output
+}
+
+fn format_subtree(tree: &Tree, productions: &[Production], indent: usize, output: &mut String) {
+    match tree {
+        Tree::Leaf(t) => *output += &format!("{}{}\n", "  ".repeat(indent), t),
+        Tree::Inner(k, children) => {
+            *output += &format!("{}{}\n", "  ".repeat(indent), productions[*k].left);
+            if children.is_empty() {
+                *output += &format!("{}{}\n", "  ".repeat(indent + 1), crate::EPSILON);
+            }
+            for c in children {
+                format_subtree(c, productions, indent + 1, output);
+            }
+        }
+    }
+}
@@ -26,8 +26,8 @@ struct Dfs {
 impl Dfs {
     fn new(mono: HashMap<String, HashSet<String>>, con: HashMap<String, HashSet<String>>) -> Dfs {
         Dfs {
-            mono: mono,
-            con: con,
+            mono,
+            con,
             visited: HashSet::new(),
             path: Vec::new(),
             element: HashMap::new(),
@@ -53,7 +53,7 @@ impl Dfs {
         for nt in self.mono.to_owned().keys() {
             self.visited.clear();
             self.path.clear();
-            let mapnt = map.entry(nt.to_owned()).or_insert(HashSet::new());
+            let mapnt = map.entry(nt.to_owned()).or_default();
             self.dfs_map(nt.clone(), mapnt);
         }
         map
@@ -87,9 +87,9 @@ impl Dfs {
     fn dfs_conn(&mut self, node: String, parent: Option<String>) {
         // DFS pre visited -- do not visit again
         // no looped element is added in the next time.
-        if !self.path.contains(&node) && parent.is_some() {
-            let nodecate = self.element[&parent.unwrap()].clone();
-            let children = self.tree.entry(nodecate).or_insert(HashSet::new());
+        if let (false, Some(parent)) = (self.path.contains(&node), parent) {
+            let nodecate = self.element[&parent];
+            let children = self.tree.entry(nodecate).or_default();
             children.insert(self.element[&node]);
         }
         if !self.visited.contains(&node) {
@@ -146,7 +146,7 @@ impl Dfs {
                 // move to the same new category.
                 let oldnum = self.element[&self.path[i]];
                 let oldcate = self.category[&oldnum].clone();
-                let newcate = self.category.entry(cate).or_insert(HashSet::new());
+                let newcate = self.category.entry(cate).or_default();
                 for el in oldcate {
                     self.element.insert(el.clone(), cate);
                     newcate.insert(el.clone());
@@ -158,7 +158,7 @@ impl Dfs {
         } else if !self.element.contains_key(&node) {
             // otherwise, it is a new category if it is not recorded.
             self.element.insert(node.clone(), cate);
-            let newcate = self.category.entry(cate).or_insert(HashSet::new());
+            let newcate = self.category.entry(cate).or_default();
             newcate.insert(node.clone());
         } else {
             self.top -= 1;
@@ -186,18 +186,18 @@ impl Dfs {
 /// By create new `Dfs` struct and call `dfs()` function,
 /// the following process is proceed:
 /// - The first DFS: `dfs_merge()` will use
-/// a union-find way to detect the non-terminals like 
-/// `
-/// S1 subseteq S2 subseteq ... subseteq Sn subseteq S1
-/// `
-/// and make them in the same category.
+///   a union-find way to detect the non-terminals like 
+///   `
+///   S1 subseteq S2 subseteq ... subseteq Sn subseteq S1
+///   `
+///   and make them in the same category.
 /// - The second DFS: `dfs_conn()` will make connections
-/// between the categories detected in the previous stage.
-/// After this stage, there will be no loop in the dependency
-/// graph, i.e., a tree.
+///   between the categories detected in the previous stage.
+///   After this stage, there will be no loop in the dependency
+///   graph, i.e., a tree.
 /// - The third DFS: `dfs_map()` will perform DFS
-/// on every non-terminals in the category relation tree
-/// to get the corresponding VT set for the non-terminal.
+///   on every non-terminals in the category relation tree
+///   to get the corresponding VT set for the non-terminal.
 ///
 pub fn compose_elements(
     mono: &HashMap<String, HashSet<String>>,
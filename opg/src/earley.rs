@@ -0,0 +1,344 @@
+//! # earley
+//!
+//! `earley` provides the Earley parser for
+//! any context-free grammar, including the
+//! ambiguous ones and the ones with ε, and
+//! reports the parses as a shared packed parse forest.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::ll1;
+use crate::Production;
+
+///
+/// An Earley item, i.e. a production with a dot
+/// and the position where it starts.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Item {
+    /// the index of the production.
+    pub prod: usize,
+    /// the position of the dot in the right side.
+    pub dot: usize,
+    /// the position in the input where the item starts.
+    pub origin: usize,
+}
+
+///
+/// A node of the forest, i.e. a symbol
+/// and the span of the input it derives.
+///
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum Node {
+    /// a terminal at the position.
+    Terminal(String, usize),
+    /// a non-terminal from the start to the end.
+    Symbol(String, usize, usize),
+}
+
+impl Node {
+    ///
+    /// Output the node like `(E, 0, 3)` or `+`.
+    ///
+    pub fn format(&self) -> String {
+        match self {
+            Node::Terminal(t, _) => t.clone(),
+            Node::Symbol(s, i, j) => format!("({}, {}, {})", s, i, j),
+        }
+    }
+}
+
+///
+/// A shared packed parse forest.
+///
+/// Every symbol node is shared by all the parses,
+/// and keeps the packed alternatives, each of which
+/// is a production with the children nodes.
+///
+pub struct Forest {
+    /// the root `(S, 0, n)`.
+    pub root: Node,
+    /// the packed alternatives of every symbol node.
+    pub packed: HashMap<Node, Vec<(usize, Vec<Node>)>>,
+}
+
+///
+/// The Earley parser over the productions.
+///
+pub struct Earley<'a> {
+    productions: &'a [Production],
+    nts: HashSet<String>,
+    nullable: HashSet<String>,
+    /// the item sets for every position.
+    pub chart: Vec<Vec<Item>>,
+    tokens: Vec<String>,
+}
+
+impl<'a> Earley<'a> {
+    ///
+    /// Create the parser for the productions.
+    ///
+    /// The starting non-terminal is the left side
+    /// of the first production.
+    ///
+    pub fn new(productions: &'a [Production], nts: &HashSet<String>) -> Earley<'a> {
+        Earley {
+            productions,
+            nts: nts.clone(),
+            nullable: ll1::gen_nullable(productions),
+            chart: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, k: usize, item: Item) {
+        if !self.chart[k].contains(&item) {
+            self.chart[k].push(item);
+        }
+    }
+
+    ///
+    /// Recognize the tokens, without `$`.
+    ///
+    /// ## Principles
+    /// For every position k, repeat on the items in set k:
+    /// - Predict: for `A -> x . B y`, add `B -> . z` at k.
+    ///   If B is nullable, also add `A -> x B . y`.
+    /// - Complete: for `B -> z .` from j, add `A -> x B . y`
+    ///   for every `A -> x . B y` in set j.
+    /// - Scan: for `A -> x . a y` where `a` is the k-th token,
+    ///   add `A -> x a . y` to set k+1.
+    ///
+    /// The input is accepted if set n has a completed
+    /// production of the starting non-terminal from 0.
+    ///
+    pub fn recognize(&mut self, tokens: &[String]) -> bool {
+        let n = tokens.len();
+        self.tokens = tokens.to_vec();
+        self.chart = vec![Vec::new(); n + 1];
+        let start = &self.productions[0].left;
+        for (i, p) in self.productions.iter().enumerate() {
+            if p.left == *start {
+                self.add(0, Item { prod: i, dot: 0, origin: 0 });
+            }
+        }
+        for k in 0..=n {
+            let mut i = 0;
+            while i < self.chart[k].len() {
+                let item = self.chart[k][i];
+                let p = &self.productions[item.prod];
+                match p.right.get(item.dot) {
+                    Some(b) if self.nts.contains(b) => {
+                        for (j, q) in self.productions.iter().enumerate() {
+                            if q.left == *b {
+                                self.add(k, Item { prod: j, dot: 0, origin: k });
+                            }
+                        }
+                        if self.nullable.contains(b) {
+                            self.add(k, Item { dot: item.dot + 1, ..item });
+                        }
+                    }
+                    Some(a) => {
+                        if tokens.get(k) == Some(a) {
+                            self.add(k + 1, Item { dot: item.dot + 1, ..item });
+                        }
+                    }
+                    None => {
+                        let origin = item.origin;
+                        let mut j = 0;
+                        while j < self.chart[origin].len() {
+                            let waiting = self.chart[origin][j];
+                            if self.productions[waiting.prod].right.get(waiting.dot) == Some(&p.left) {
+                                self.add(k, Item { dot: waiting.dot + 1, ..waiting });
+                            }
+                            j += 1;
+                        }
+                    }
+                }
+                i += 1;
+            }
+        }
+        self.accepted()
+    }
+
+    fn accepted(&self) -> bool {
+        let start = &self.productions[0].left;
+        self.chart.last().is_some_and(|set| {
+            set.iter().any(|item| {
+                let p = &self.productions[item.prod];
+                p.left == *start && item.origin == 0 && item.dot == p.right.len()
+            })
+        })
+    }
+
+    ///
+    /// Build the shared packed parse forest
+    /// after `recognize()` accepts.
+    ///
+    /// ## Principles
+    /// The completed items give the symbol nodes `(A, i, j)`.
+    /// From the root `(S, 0, n)`, split the span of every node
+    /// into the right side of each production of `A`,
+    /// where a terminal matches the token and a non-terminal
+    /// matches a completed node. Every split is a packed
+    /// alternative of the node.
+    ///
+    pub fn forest(&self) -> Option<Forest> {
+        if !self.accepted() {
+            return None;
+        }
+        let mut completed: HashSet<(String, usize, usize)> = HashSet::new();
+        for (j, set) in self.chart.iter().enumerate() {
+            for item in set {
+                let p = &self.productions[item.prod];
+                if item.dot == p.right.len() {
+                    completed.insert((p.left.clone(), item.origin, j));
+                }
+            }
+        }
+        let root = Node::Symbol(self.productions[0].left.clone(), 0, self.tokens.len());
+        let mut packed: HashMap<Node, Vec<(usize, Vec<Node>)>> = HashMap::new();
+        let mut work: Vec<Node> = vec![root.clone()];
+        while let Some(node) = work.pop() {
+            let (a, i, j) = match &node {
+                Node::Symbol(a, i, j) if !packed.contains_key(&node) => (a.clone(), *i, *j),
+                _ => continue,
+            };
+            let mut alts: Vec<(usize, Vec<Node>)> = Vec::new();
+            for (k, p) in self.productions.iter().enumerate() {
+                if p.left == a && completed.contains(&(a.clone(), i, j)) {
+                    let mut children: Vec<Node> = Vec::new();
+                    self.split(&p.right, i, j, &completed, &mut children, &mut |c| {
+                        alts.push((k, c.to_vec()));
+                    });
+                }
+            }
+            for (_, children) in alts.iter() {
+                work.extend(children.iter().cloned());
+            }
+            packed.insert(node, alts);
+        }
+        Some(Forest { root, packed })
+    }
+
+    ///
+    /// Split the span `[pos, end)` into the symbols.
+    ///
+    fn split<F>(
+        &self,
+        symbols: &[String],
+        pos: usize,
+        end: usize,
+        completed: &HashSet<(String, usize, usize)>,
+        children: &mut Vec<Node>,
+        found: &mut F,
+    ) where
+        F: FnMut(&[Node]),
+    {
+        let x = match symbols.first() {
+            Some(x) => x,
+            None => {
+                if pos == end {
+                    found(children);
+                }
+                return;
+            }
+        };
+        if !self.nts.contains(x) {
+            if pos < end && self.tokens[pos] == *x {
+                children.push(Node::Terminal(x.clone(), pos));
+                self.split(&symbols[1..], pos + 1, end, completed, children, found);
+                children.pop();
+            }
+            return;
+        }
+        for e in pos..=end {
+            if completed.contains(&(x.clone(), pos, e)) {
+                children.push(Node::Symbol(x.clone(), pos, e));
+                self.split(&symbols[1..], e, end, completed, children, found);
+                children.pop();
+            }
+        }
+    }
+}
+
+impl Forest {
+    ///
+    /// Count the distinct derivations.
+    ///
+    /// ## Return
+    /// The number of parse trees, or `None` if a cycle
+    /// like `A => A` gives infinitely many.
+    ///
+    /// ## Principles
+    /// The count of a node is the sum over its packed
+    /// alternatives of the product of the children counts.
+    ///
+    pub fn count(&self) -> Option<u128> {
+        let mut memo: HashMap<Node, u128> = HashMap::new();
+        let mut path: HashSet<Node> = HashSet::new();
+        self.count_node(&self.root, &mut memo, &mut path)
+    }
+
+    fn count_node(&self, node: &Node, memo: &mut HashMap<Node, u128>, path: &mut HashSet<Node>) -> Option<u128> {
+        if let Node::Terminal(..) = node {
+            return Some(1);
+        }
+        if let Some(c) = memo.get(node) {
+            return Some(*c);
+        }
+        if !path.insert(node.clone()) {
+            return None;
+        }
+        let mut total: u128 = 0;
+        for (_, children) in self.packed[node].iter() {
+            let mut product: u128 = 1;
+            for child in children {
+                product = product.saturating_mul(self.count_node(child, memo, path)?);
+            }
+            total = total.saturating_add(product);
+        }
+        path.remove(node);
+        memo.insert(node.clone(), total);
+        Some(total)
+    }
+
+    ///
+    /// Format the forest, one symbol node
+    /// with its packed alternatives at a time.
+    ///
+    pub fn format(&self, productions: &[Production]) -> String {
+        let mut nodes: Vec<&Node> = self.packed.keys().collect();
+        nodes.sort_by_key(|n| match n {
+            Node::Symbol(s, i, j) => (*i, usize::MAX - *j, s.clone()),
+            Node::Terminal(t, i) => (*i, 0, t.clone()),
+        });
+        let mut output = String::new();
+        for node in nodes {
+            output += &format!("{}\n", node.format());
+            for (k, children) in self.packed[node].iter() {
+                let cs: Vec<String> = children.iter().map(|c| c.format()).collect();
+                output += &format!("\t{}:\t{}\n", productions[*k], cs.join(" "));
+            }
+        }
+        output
+    }
+}
+
+///
+/// Format the item sets of the chart.
+///
+pub fn format_chart(productions: &[Production], chart: &[Vec<Item>]) -> String {
+    let mut output = String::new();
+    for (k, set) in chart.iter().enumerate() {
+        output += &format!("S{}:\n", k);
+        for item in set {
+            let p = &productions[item.prod];
+            let mut symbols: Vec<&str> = p.right.iter().map(|s| s.as_str()).collect();
+            symbols.insert(item.dot, ".");
+            output += &format!("\t{} -> {}, {}\n", p.left, symbols.join(" "), item.origin);
+        }
+    }
+    output
+}
@@ -0,0 +1,148 @@
+//! # grammar
+//!
+//! `grammar` provides `GrammarBuilder` to
+//! construct the productions in code, instead
+//! of formatting the text for `gen_productions()`.
+
+use crate::{Production, EPSILON};
+
+///
+/// A builder of the productions.
+///
+/// ## Example
+/// ```
+/// let productions = GrammarBuilder::new()
+///     .rule("E", &["E", "+", "T"])
+///     .alt(&["T"])
+///     .rule("T", &["T", "*", "F"])
+///     .alt(&["F"])
+///     .rule("F", &["(", "E", ")"])
+///     .alt(&["i"])
+///     .start("E")
+///     .build()?;
+/// let table = gen_table(&productions);
+/// ```
+///
+#[derive(Default)]
+pub struct GrammarBuilder {
+    productions: Vec<Production>,
+    /// the left side of the last rule.
+    current: Option<String>,
+    start: Option<String>,
+    /// the first error met.
+    error: Option<String>,
+}
+
+///
+/// Check a symbol, which should be written
+/// in the grammar file in the same way.
+///
+fn check_symbol(v: &str) -> Result<(), String> {
+    if v.is_empty() {
+        return Err("empty symbol".to_string());
+    }
+    if v.chars().any(char::is_whitespace) {
+        return Err(format!("symbol '{}' contains whitespace", v));
+    }
+    if v == "$" {
+        return Err("symbol '$' is the end of the input".to_string());
+    }
+    if v.contains("->") || v.contains('|') || v.contains('{') || v.contains('}') || v.contains("@(") {
+        return Err(format!("symbol '{}' contains a delimiter of the grammar", v));
+    }
+    if v.starts_with('%') {
+        return Err(format!("symbol '{}' starts like a directive", v));
+    }
+    Ok(())
+}
+
+impl GrammarBuilder {
+    ///
+    /// Create an empty builder.
+    ///
+    pub fn new() -> GrammarBuilder {
+        GrammarBuilder::default()
+    }
+
+    fn push(&mut self, left: &str, right: &[&str]) {
+        if self.error.is_some() {
+            return;
+        }
+        // a single ε is the empty right side as in the file
+        let right: &[&str] = if right == [EPSILON] { &[] } else { right };
+        if left == EPSILON || right.contains(&EPSILON) {
+            self.error = Some(format!("'{}' should be the only symbol of a right side", EPSILON));
+            return;
+        }
+        for v in std::iter::once(&left).chain(right.iter()) {
+            if let Err(e) = check_symbol(v) {
+                self.error = Some(e);
+                return;
+            }
+        }
+        let right: Vec<String> = right.iter().map(|v| v.to_string()).collect();
+        if self.productions.iter().any(|p| p.left == left && p.right == right) {
+            self.error = Some(format!("duplicate production '{}'", Production::new(left, right)));
+            return;
+        }
+        self.productions.push(Production::new(left, right));
+    }
+
+    ///
+    /// Add the production `left -> right`,
+    /// and the following `alt()` is also for `left`.
+    ///
+    pub fn rule(mut self, left: &str, right: &[&str]) -> GrammarBuilder {
+        self.current = Some(left.to_string());
+        self.push(left, right);
+        self
+    }
+
+    ///
+    /// Add another alternative for the left side of the last rule.
+    ///
+    pub fn alt(mut self, right: &[&str]) -> GrammarBuilder {
+        match self.current.clone() {
+            Some(left) => self.push(&left, right),
+            None => {
+                self.error.get_or_insert("alternative before any rule".to_string());
+            }
+        }
+        self
+    }
+
+    ///
+    /// Set the starting non-terminal,
+    /// the left side of the first rule by default.
+    ///
+    pub fn start(mut self, start: &str) -> GrammarBuilder {
+        self.start = Some(start.to_string());
+        self
+    }
+
+    ///
+    /// Build the productions.
+    ///
+    /// ## Return
+    /// The productions, where the ones of the starting
+    /// non-terminal come first as `gen_productions()`
+    /// requires, or the first error met.
+    ///
+    pub fn build(self) -> Result<Vec<Production>, String> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        if self.productions.is_empty() {
+            return Err("the grammar is empty".to_string());
+        }
+        let mut productions = self.productions;
+        if let Some(start) = self.start {
+            if !productions.iter().any(|p| p.left == start) {
+                return Err(format!("the starting non-terminal '{}' has no rule", start));
+            }
+            // stable, so the order of the rest is kept
+            productions.sort_by_key(|p| p.left != start);
+        }
+        Ok(productions)
+    }
+}
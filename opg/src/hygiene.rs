@@ -0,0 +1,205 @@
+//! # hygiene
+//!
+//! `hygiene` finds the useless parts of a grammar,
+//! i.e. the non-terminals deriving no terminal string,
+//! the unreachable ones, the unused terminals and
+//! the duplicate productions, and removes them.
+
+use std::collections::HashSet;
+
+use crate::{get_non_terminals, Production};
+
+///
+/// Generate the productive non-terminals.
+///
+/// ## Principles
+/// A is productive if there is `A -> x` where every
+/// symbol in x is a terminal or productive. Repeat
+/// until nothing changes.
+///
+pub fn gen_productive(productions: &[Production], nts: &HashSet<String>) -> HashSet<String> {
+    let mut productive: HashSet<String> = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for p in productions {
+            if !productive.contains(&p.left) && p.right.iter().all(|v| !nts.contains(v) || productive.contains(v)) {
+                productive.insert(p.left.clone());
+                changed = true;
+            }
+        }
+    }
+    productive
+}
+
+///
+/// Generate the symbols reachable from `start`,
+/// including the terminals.
+///
+pub fn gen_reachable(productions: &[Production], start: &str) -> HashSet<String> {
+    let mut reachable: HashSet<String> = HashSet::new();
+    reachable.insert(start.to_string());
+    let mut work: Vec<String> = vec![start.to_string()];
+    while let Some(a) = work.pop() {
+        for p in productions.iter().filter(|p| p.left == a) {
+            for v in p.right.iter() {
+                if reachable.insert(v.clone()) {
+                    work.push(v.clone());
+                }
+            }
+        }
+    }
+    reachable
+}
+
+///
+/// The hygiene report of a grammar,
+/// every list in the order of appearance.
+///
+pub struct Report {
+    /// the start symbol.
+    pub start: String,
+    /// the non-terminals deriving no terminal string.
+    pub unproductive: Vec<String>,
+    /// the productive non-terminals unreachable from the start.
+    pub unreachable: Vec<String>,
+    /// the terminals only used in the useless productions,
+    /// or only declared in the precedence directives.
+    pub unused: Vec<String>,
+    /// the indices of the productions appearing before.
+    pub duplicates: Vec<usize>,
+}
+
+impl Report {
+    ///
+    /// Whether nothing is found.
+    ///
+    pub fn is_clean(&self) -> bool {
+        self.unproductive.is_empty() && self.unreachable.is_empty() && self.unused.is_empty() && self.duplicates.is_empty()
+    }
+
+    ///
+    /// Output one warning for every finding.
+    ///
+    pub fn warnings(&self, productions: &[Production]) -> Vec<String> {
+        let mut warnings: Vec<String> = Vec::new();
+        for a in self.unproductive.iter() {
+            warnings.push(format!("non-terminal '{}' derives no terminal string", a));
+        }
+        for a in self.unreachable.iter() {
+            warnings.push(format!("non-terminal '{}' is unreachable from '{}'", a, self.start));
+        }
+        for t in self.unused.iter() {
+            warnings.push(format!("terminal '{}' is unused", t));
+        }
+        for i in self.duplicates.iter() {
+            warnings.push(format!("duplicate production '{}'", productions[*i]));
+        }
+        warnings
+    }
+}
+
+fn push_new(v: &mut Vec<String>, s: &str) {
+    if !v.iter().any(|x| x == s) {
+        v.push(s.to_string());
+    }
+}
+
+///
+/// Check the hygiene of the grammar.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `declared` the terminals declared elsewhere,
+/// like the ones in `%left`.
+///
+/// ## Return
+/// The `Report` of the grammar.
+///
+/// ## Example
+/// ```
+/// let report = hygiene::check(&productions, &[]);
+/// for w in report.warnings(&productions) {
+///     eprintln!("warning: {}", w);
+/// }
+/// ```
+///
+/// ## Principles
+/// The unproductive non-terminals are found first, then
+/// the unreachable ones without the productions using
+/// the unproductive ones, as removing in the other order
+/// could leave useless symbols.
+///
+pub fn check(productions: &[Production], declared: &[String]) -> Report {
+    let nts = get_non_terminals(productions);
+    let start = productions.first().map(|p| p.left.clone()).unwrap_or_default();
+    let productive = gen_productive(productions, &nts);
+    let useful: Vec<Production> = productions
+        .iter()
+        .filter(|p| productive.contains(&p.left) && p.right.iter().all(|v| !nts.contains(v) || productive.contains(v)))
+        .cloned()
+        .collect();
+    let reachable = gen_reachable(&useful, &start);
+
+    let mut report = Report {
+        start: start.clone(),
+        unproductive: Vec::new(),
+        unreachable: Vec::new(),
+        unused: Vec::new(),
+        duplicates: Vec::new(),
+    };
+    for (i, p) in productions.iter().enumerate() {
+        let symbols = std::iter::once(&p.left).chain(p.right.iter());
+        for v in symbols {
+            if !nts.contains(v) {
+                if !reachable.contains(v) {
+                    push_new(&mut report.unused, v);
+                }
+            } else if !productive.contains(v) {
+                push_new(&mut report.unproductive, v);
+            } else if !reachable.contains(v) {
+                push_new(&mut report.unreachable, v);
+            }
+        }
+        if productions[..i].iter().any(|q| q.left == p.left && q.right == p.right) {
+            report.duplicates.push(i);
+        }
+    }
+    let used: HashSet<&String> = productions.iter().flat_map(|p| p.right.iter()).collect();
+    for t in declared {
+        if !used.contains(t) {
+            push_new(&mut report.unused, t);
+        }
+    }
+    report
+}
+
+///
+/// Remove the useless productions.
+///
+/// ## Return
+/// The productions without duplicates, and without the
+/// unproductive or unreachable non-terminals. It is empty
+/// if the starting non-terminal is unproductive.
+///
+pub fn remove_useless(productions: &[Production]) -> Vec<Production> {
+    let nts = get_non_terminals(productions);
+    let start = match productions.first() {
+        Some(p) => p.left.clone(),
+        None => return Vec::new(),
+    };
+    let productive = gen_productive(productions, &nts);
+    let mut useful: Vec<Production> = Vec::new();
+    for p in productions {
+        if productive.contains(&p.left)
+            && p.right.iter().all(|v| !nts.contains(v) || productive.contains(v))
+            && !useful.iter().any(|q| q.left == p.left && q.right == p.right)
+        {
+            useful.push(p.clone());
+        }
+    }
+    let reachable = gen_reachable(&useful, &start);
+    useful.retain(|p| reachable.contains(&p.left));
+    useful
+}
@@ -0,0 +1,254 @@
+//! # interop
+//!
+//! `interop` provides the common parts for the
+//! grammar files of the other tools, i.e. a scanner
+//! shared by bison and ANTLR, and the naming of the
+//! symbols as their identifiers.
+
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+
+///
+/// A token of the grammar file.
+///
+#[derive(Clone, PartialEq, Debug)]
+pub enum Tok {
+    /// an identifier like `expr` or `NUM`.
+    Ident(String),
+    /// a quoted literal, with the escapes resolved.
+    Literal(String),
+    /// a directive like `%prec` or `%%`.
+    Directive(String),
+    /// a code block in braces, or `%{ ... %}`.
+    Block,
+    /// a tag in angle brackets like `<ival>`.
+    Tag(String),
+    /// a set in square brackets like `[a-z]`.
+    Set(String),
+    Punct(char),
+}
+
+impl Display for Tok {
+    ///
+    /// Output the token for the error messages.
+    ///
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Tok::Ident(s) | Tok::Directive(s) => write!(f, "'{}'", s),
+            Tok::Literal(s) => write!(f, "literal '{}'", s),
+            Tok::Block => write!(f, "a block"),
+            Tok::Tag(s) => write!(f, "'<{}>'", s),
+            Tok::Set(s) => write!(f, "'[{}]'", s),
+            Tok::Punct(c) => write!(f, "'{}'", c),
+        }
+    }
+}
+
+///
+/// Split the text into tokens with their line numbers,
+/// skipping the whitespace and the comments.
+///
+pub fn scan(text: &str) -> Result<Vec<(Tok, usize)>, String> {
+    let cs: Vec<char> = text.chars().collect();
+    let mut tokens: Vec<(Tok, usize)> = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    // skip to the end of `close`, counting the lines
+    let skip_to = |i: &mut usize, line: &mut usize, close: &str| -> bool {
+        let close: Vec<char> = close.chars().collect();
+        while *i < cs.len() {
+            if cs[*i..].starts_with(&close) {
+                *i += close.len();
+                return true;
+            }
+            if cs[*i] == '\n' {
+                *line += 1;
+            }
+            *i += 1;
+        }
+        false
+    };
+    while i < cs.len() {
+        let c = cs[i];
+        let start = line;
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if cs[i..].starts_with(&['/', '*']) {
+            i += 2;
+            if !skip_to(&mut i, &mut line, "*/") {
+                return Err(format!("line {}: unterminated comment", start));
+            }
+        } else if cs[i..].starts_with(&['/', '/']) {
+            while i < cs.len() && cs[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '\'' || c == '"' {
+            let (s, next) = literal(&cs, i).ok_or(format!("line {}: unterminated literal", start))?;
+            tokens.push((Tok::Literal(s), start));
+            i = next;
+        } else if c == '{' {
+            i = block(&cs, i, &mut line).ok_or(format!("line {}: unbalanced braces", start))?;
+            tokens.push((Tok::Block, start));
+        } else if cs[i..].starts_with(&['%', '{']) {
+            i += 2;
+            if !skip_to(&mut i, &mut line, "%}") {
+                return Err(format!("line {}: unterminated '%{{'", start));
+            }
+            tokens.push((Tok::Block, start));
+        } else if c == '%' {
+            let mut j = i + 1;
+            if cs.get(j) == Some(&'%') {
+                j += 1;
+            } else {
+                while j < cs.len() && (cs[j].is_alphanumeric() || cs[j] == '_' || cs[j] == '-') {
+                    j += 1;
+                }
+            }
+            tokens.push((Tok::Directive(cs[i..j].iter().collect()), start));
+            i = j;
+        } else if c == '<' || c == '[' {
+            let close = if c == '<' { '>' } else { ']' };
+            let mut j = i + 1;
+            while j < cs.len() && cs[j] != close && cs[j] != '\n' {
+                j += if cs[j] == '\\' { 2 } else { 1 };
+            }
+            if j >= cs.len() || cs[j] != close {
+                return Err(format!("line {}: unterminated '{}'", start, c));
+            }
+            let s: String = cs[i + 1..j].iter().collect();
+            tokens.push((if c == '<' { Tok::Tag(s) } else { Tok::Set(s) }, start));
+            i = j + 1;
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut j = i;
+            while j < cs.len() && (cs[j].is_alphanumeric() || cs[j] == '_') {
+                j += 1;
+            }
+            tokens.push((Tok::Ident(cs[i..j].iter().collect()), start));
+            i = j;
+        } else {
+            tokens.push((Tok::Punct(c), start));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+///
+/// Read the literal quoted at `cs[i]`.
+///
+/// ## Return
+/// The unescaped literal and the index after it.
+///
+fn literal(cs: &[char], i: usize) -> Option<(String, usize)> {
+    let quote = cs[i];
+    let mut s = String::new();
+    let mut j = i + 1;
+    loop {
+        match *cs.get(j)? {
+            '\n' => return None,
+            c if c == quote => return Some((s, j + 1)),
+            '\\' => {
+                j += 1;
+                match *cs.get(j)? {
+                    'n' => s.push('\n'),
+                    't' => s.push('\t'),
+                    'r' => s.push('\r'),
+                    'u' => {
+                        let hex: String = cs.get(j + 1..j + 5)?.iter().collect();
+                        s.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                        j += 4;
+                    }
+                    c => s.push(c),
+                }
+            }
+            c => s.push(c),
+        }
+        j += 1;
+    }
+}
+
+///
+/// Skip the block in braces at `cs[i]`, where the
+/// braces in the literals and the comments are not counted.
+///
+/// ## Return
+/// The index after the block.
+///
+fn block(cs: &[char], i: usize, line: &mut usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut j = i;
+    while j < cs.len() {
+        match cs[j] {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j + 1);
+                }
+            }
+            '\'' | '"' => {
+                j = literal(cs, j)?.1;
+                continue;
+            }
+            '/' if cs.get(j + 1) == Some(&'*') => {
+                j += 2;
+                while j < cs.len() && !cs[j..].starts_with(&['*', '/']) {
+                    *line += (cs[j] == '\n') as usize;
+                    j += 1;
+                }
+                j += 1;
+            }
+            '/' if cs.get(j + 1) == Some(&'/') => {
+                while j + 1 < cs.len() && cs[j + 1] != '\n' {
+                    j += 1;
+                }
+            }
+            '\n' => *line += 1,
+            _ => {}
+        }
+        j += 1;
+    }
+    None
+}
+
+///
+/// Name a symbol as an identifier, where the
+/// other characters are replaced by `_`.
+///
+pub fn identifier(v: &str) -> String {
+    let mut name: String = v
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, 'n');
+    }
+    name
+}
+
+///
+/// Make the name unique among `used` by
+/// appending a number, and record it.
+///
+pub fn unique(name: String, used: &mut HashSet<String>) -> String {
+    let mut unique = name.clone();
+    let mut k = 1;
+    while !used.insert(unique.clone()) {
+        k += 1;
+        unique = format!("{}{}", name, k);
+    }
+    unique
+}
+
+///
+/// Name a literal as a terminal of `opg`, where the
+/// control characters are written as the escapes like `\n`.
+///
+pub fn terminal(literal: &str) -> String {
+    literal
+        .chars()
+        .map(|c| if c.is_control() { c.escape_default().to_string() } else { c.to_string() })
+        .collect()
+}
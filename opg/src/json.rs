@@ -0,0 +1,511 @@
+//! # json
+//!
+//! `json` writes a run of `opg` as a JSON document,
+//! i.e. the grammar, the sets and the table, and reads
+//! it back into an `OpTable` without recomputation.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+use crate::table::OpTable;
+use crate::{gen_firstvt, gen_lastvt, gen_table, get_non_terminals, ordered_symbols, Production};
+
+/// The version of the document, which
+/// changes with an incompatible format.
+pub const VERSION: u64 = 1;
+
+///
+/// A JSON value, where the members
+/// of an object are kept in order.
+///
+#[derive(Clone, PartialEq, Debug)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    ///
+    /// Get the member of an object.
+    ///
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    fn is_scalar(&self) -> bool {
+        !matches!(self, Value::Array(_) | Value::Object(_))
+    }
+
+    fn write(&self, f: &mut Formatter, indent: usize) -> fmt::Result {
+        let pad = "  ".repeat(indent + 1);
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write_string(f, s),
+            Value::Array(items) if items.is_empty() => write!(f, "[]"),
+            // an array of scalars is kept in a line, like a row of the matrix
+            Value::Array(items) if items.iter().all(Value::is_scalar) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    item.write(f, indent)?;
+                }
+                write!(f, "]")
+            }
+            Value::Array(items) => {
+                writeln!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    write!(f, "{}", pad)?;
+                    item.write(f, indent + 1)?;
+                    writeln!(f, "{}", if i + 1 < items.len() { "," } else { "" })?;
+                }
+                write!(f, "{}]", "  ".repeat(indent))
+            }
+            Value::Object(members) if members.is_empty() => write!(f, "{{}}"),
+            Value::Object(members) => {
+                writeln!(f, "{{")?;
+                for (i, (k, v)) in members.iter().enumerate() {
+                    write!(f, "{}", pad)?;
+                    write_string(f, k)?;
+                    write!(f, ": ")?;
+                    v.write(f, indent + 1)?;
+                    writeln!(f, "{}", if i + 1 < members.len() { "," } else { "" })?;
+                }
+                write!(f, "{}}}", "  ".repeat(indent))
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+impl Display for Value {
+    ///
+    /// Output the value with the indents.
+    ///
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+///
+/// Parse a JSON text.
+///
+/// ## Return
+/// The value, or the error with the offset, where the arrays
+/// and the objects are nested at most `MAX_DEPTH` levels.
+///
+/// ## Example
+/// ```
+/// let value = json::parse(r#"{"terminals": ["+", "$"]}"#)?;
+/// ```
+///
+pub fn parse(text: &str) -> Result<Value, String> {
+    let cs: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    let value = parse_value(&cs, &mut i, 0)?;
+    skip_whitespace(&cs, &mut i);
+    if i < cs.len() {
+        return Err(format!("unexpected '{}' at {}", cs[i], i));
+    }
+    Ok(value)
+}
+
+fn skip_whitespace(cs: &[char], i: &mut usize) {
+    while *i < cs.len() && matches!(cs[*i], ' ' | '\t' | '\n' | '\r') {
+        *i += 1;
+    }
+}
+
+fn expect(cs: &[char], i: &mut usize, c: char) -> Result<(), String> {
+    skip_whitespace(cs, i);
+    match cs.get(*i) {
+        Some(d) if *d == c => {
+            *i += 1;
+            Ok(())
+        }
+        Some(d) => Err(format!("expected '{}' at {}, found '{}'", c, i, d)),
+        None => Err(format!("expected '{}' at the end", c)),
+    }
+}
+
+///
+/// The maximum nesting of the arrays and the objects,
+/// which keeps `parse_value()` from overflowing the stack.
+///
+const MAX_DEPTH: usize = 512;
+
+fn parse_value(cs: &[char], i: &mut usize, depth: usize) -> Result<Value, String> {
+    skip_whitespace(cs, i);
+    let start = *i;
+    match cs.get(*i) {
+        None => Err("unexpected end of the text".to_string()),
+        Some('{') | Some('[') if depth >= MAX_DEPTH => Err(format!("nesting too deep at {}", start)),
+        Some('{') => {
+            *i += 1;
+            let mut members: Vec<(String, Value)> = Vec::new();
+            skip_whitespace(cs, i);
+            if cs.get(*i) == Some(&'}') {
+                *i += 1;
+                return Ok(Value::Object(members));
+            }
+            loop {
+                skip_whitespace(cs, i);
+                let key = match parse_value(cs, i, depth + 1)? {
+                    Value::String(key) => key,
+                    _ => return Err(format!("expected a key at {}", start)),
+                };
+                expect(cs, i, ':')?;
+                members.push((key, parse_value(cs, i, depth + 1)?));
+                skip_whitespace(cs, i);
+                match cs.get(*i) {
+                    Some(',') => *i += 1,
+                    _ => {
+                        expect(cs, i, '}')?;
+                        return Ok(Value::Object(members));
+                    }
+                }
+            }
+        }
+        Some('[') => {
+            *i += 1;
+            let mut items: Vec<Value> = Vec::new();
+            skip_whitespace(cs, i);
+            if cs.get(*i) == Some(&']') {
+                *i += 1;
+                return Ok(Value::Array(items));
+            }
+            loop {
+                items.push(parse_value(cs, i, depth + 1)?);
+                skip_whitespace(cs, i);
+                match cs.get(*i) {
+                    Some(',') => *i += 1,
+                    _ => {
+                        expect(cs, i, ']')?;
+                        return Ok(Value::Array(items));
+                    }
+                }
+            }
+        }
+        Some('"') => {
+            *i += 1;
+            let mut s = String::new();
+            loop {
+                match cs.get(*i) {
+                    None => return Err(format!("unterminated string at {}", start)),
+                    Some('"') => break,
+                    Some('\\') => {
+                        *i += 1;
+                        match cs.get(*i) {
+                            Some('"') => s.push('"'),
+                            Some('\\') => s.push('\\'),
+                            Some('/') => s.push('/'),
+                            Some('b') => s.push('\u{8}'),
+                            Some('f') => s.push('\u{c}'),
+                            Some('n') => s.push('\n'),
+                            Some('r') => s.push('\r'),
+                            Some('t') => s.push('\t'),
+                            Some('u') => s.push(parse_unicode(cs, i)?),
+                            _ => return Err(format!("invalid escape at {}", i)),
+                        }
+                    }
+                    Some(c) => s.push(*c),
+                }
+                *i += 1;
+            }
+            *i += 1;
+            Ok(Value::String(s))
+        }
+        Some(c) if *c == '-' || c.is_ascii_digit() => {
+            while *i < cs.len() && (cs[*i].is_ascii_digit() || "+-.eE".contains(cs[*i])) {
+                *i += 1;
+            }
+            let number: String = cs[start..*i].iter().collect();
+            number
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| format!("invalid number '{}' at {}", number, start))
+        }
+        Some(_) => {
+            for (word, value) in [("null", Value::Null), ("true", Value::Bool(true)), ("false", Value::Bool(false))] {
+                let w: Vec<char> = word.chars().collect();
+                if cs[*i..].starts_with(&w) {
+                    *i += w.len();
+                    return Ok(value);
+                }
+            }
+            Err(format!("unexpected '{}' at {}", cs[*i], start))
+        }
+    }
+}
+
+///
+/// Parse the `XXXX` after `\u` at `cs[i]`,
+/// with the surrogate pair like `\uD83D\uDE00`.
+///
+fn parse_unicode(cs: &[char], i: &mut usize) -> Result<char, String> {
+    let hex = |i: usize| -> Result<u32, String> {
+        let s: String = cs.get(i..i + 4).ok_or("invalid escape at the end")?.iter().collect();
+        u32::from_str_radix(&s, 16).map_err(|_| format!("invalid escape at {}", i))
+    };
+    let high = hex(*i + 1)?;
+    *i += 4;
+    if (0xD800..0xDC00).contains(&high) && cs.get(*i + 1..*i + 3) == Some(&['\\', 'u']) {
+        let low = hex(*i + 3)?;
+        if (0xDC00..0xE000).contains(&low) {
+            *i += 6;
+            return char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                .ok_or(format!("invalid escape at {}", i));
+        }
+    }
+    char::from_u32(high).ok_or(format!("invalid escape at {}", i))
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+fn strings(vs: &[String]) -> Value {
+    Value::Array(vs.iter().map(|v| string(v)).collect())
+}
+
+fn relation(c: Option<char>) -> Value {
+    c.map_or(Value::Null, |c| string(&c.to_string()))
+}
+
+///
+/// The sets like FIRSTVT as an object, where
+/// the members are in the order of the symbols.
+///
+fn sets(sets: &HashMap<String, HashSet<String>>, nv: &[String], tv: &[String]) -> Value {
+    let mut members: Vec<(String, Value)> = Vec::new();
+    for v in nv.iter() {
+        let set: Vec<String> = tv.iter().filter(|t| sets.get(v).is_some_and(|s| s.contains(*t))).cloned().collect();
+        members.push((v.clone(), strings(&set)));
+    }
+    Value::Object(members)
+}
+
+///
+/// Generate the document of the grammar.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Return
+/// The document with the members:
+/// - `version`: `VERSION`.
+/// - `productions`: the objects with `left`, `right`,
+///   `action` and `templates`, where the first `left`
+///   is the starting non-terminal.
+/// - `non_terminals`, `terminals`: in the order of
+///   appearance, where `$` is the last terminal.
+/// - `firstvt`, `lastvt`: the sets of the non-terminals.
+/// - `matrix`: the rows and the columns in the order of
+///   `terminals`, where a cell is `"<"`, `"="`, `">"` or `null`.
+/// - `functions`: the precedence functions as `f` and `g`
+///   in the order of `terminals`, or `null` if they do not exist.
+/// - `conflicts`: the objects with `left`, `right`, `kept`
+///   and `rejected`, see `OpTable::conflicts()`.
+///
+/// ## Example
+/// ```
+/// fs::write("table.json", json::document(&productions).to_string())?;
+/// ```
+///
+pub fn document(productions: &[Production]) -> Value {
+    let nts = get_non_terminals(productions);
+    let (nv, mut tv) = ordered_symbols(productions, &nts);
+    tv.push("$".to_string());
+    let table = gen_table(productions);
+
+    let productions_value: Vec<Value> = productions
+        .iter()
+        .map(|p| {
+            Value::Object(vec![
+                ("left".to_string(), string(&p.left)),
+                ("right".to_string(), strings(&p.right)),
+                ("action".to_string(), p.action.as_deref().map_or(Value::Null, string)),
+                ("templates".to_string(), strings(&p.templates)),
+            ])
+        })
+        .collect();
+    let matrix: Vec<Value> = tv
+        .iter()
+        .map(|a| Value::Array(tv.iter().map(|b| relation(table.get(a, b))).collect()))
+        .collect();
+    let numbers = |fs: &[usize]| Value::Array(fs.iter().map(|n| Value::Number(*n as f64)).collect());
+    let functions = match table.gen_functions(&tv) {
+        Some((f, g)) => Value::Object(vec![("f".to_string(), numbers(&f)), ("g".to_string(), numbers(&g))]),
+        None => Value::Null,
+    };
+    let conflicts: Vec<Value> = table
+        .conflicts()
+        .iter()
+        .map(|(a, b, kept, rejected)| {
+            Value::Object(vec![
+                ("left".to_string(), string(a)),
+                ("right".to_string(), string(b)),
+                ("kept".to_string(), relation(Some(*kept))),
+                ("rejected".to_string(), relation(Some(*rejected))),
+            ])
+        })
+        .collect();
+
+    Value::Object(vec![
+        ("version".to_string(), Value::Number(VERSION as f64)),
+        ("productions".to_string(), Value::Array(productions_value)),
+        ("non_terminals".to_string(), strings(&nv)),
+        ("terminals".to_string(), strings(&tv)),
+        ("firstvt".to_string(), sets(&gen_firstvt(productions, &nts), &nv, &tv)),
+        ("lastvt".to_string(), sets(&gen_lastvt(productions, &nts), &nv, &tv)),
+        ("matrix".to_string(), Value::Array(matrix)),
+        ("functions".to_string(), functions),
+        ("conflicts".to_string(), Value::Array(conflicts)),
+    ])
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Result<&'a Value, String> {
+    value.get(key).ok_or(format!("missing '{}'", key))
+}
+
+fn string_array(value: &Value, key: &str) -> Result<Vec<String>, String> {
+    field(value, key)?
+        .as_array()
+        .ok_or(format!("'{}' should be an array", key))?
+        .iter()
+        .map(|v| v.as_str().map(|s| s.to_string()).ok_or(format!("'{}' should be strings", key)))
+        .collect()
+}
+
+fn relation_char(value: &Value, key: &str) -> Result<Option<char>, String> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(s) if s == "<" || s == "=" || s == ">" => Ok(s.chars().next()),
+        _ => Err(format!("'{}' should be \"<\", \"=\", \">\" or null", key)),
+    }
+}
+
+///
+/// Load the productions and the `OpTable` from a
+/// document, which could be written by hand.
+///
+/// ## Return
+/// The productions and the table with the conflicts,
+/// or the error if the document is not valid.
+///
+/// ## Example
+/// ```
+/// let (productions, table) = json::load(&fs::read_to_string("table.json")?)?;
+/// ```
+///
+/// ## Principles
+/// Only `version`, `productions`, `terminals`, `matrix` and
+/// `conflicts` are read, where `action`, `templates` and
+/// `conflicts` could be missing. The sets and the functions
+/// are derived, and they are not checked.
+///
+pub fn load(text: &str) -> Result<(Vec<Production>, OpTable), String> {
+    let doc = parse(text)?;
+    match field(&doc, "version")?.as_u64() {
+        Some(VERSION) => {}
+        _ => return Err(format!("'version' should be {}", VERSION)),
+    }
+
+    let mut productions: Vec<Production> = Vec::new();
+    for p in field(&doc, "productions")?.as_array().ok_or("'productions' should be an array")? {
+        let left = field(p, "left")?.as_str().ok_or("'left' should be a string")?;
+        let mut production = Production::new(left, string_array(p, "right")?);
+        production.action = match p.get("action") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(s)) => Some(s.clone()),
+            Some(_) => return Err("'action' should be a string or null".to_string()),
+        };
+        if p.get("templates").is_some() {
+            production.templates = string_array(p, "templates")?;
+        }
+        productions.push(production);
+    }
+
+    let tv = string_array(&doc, "terminals")?;
+    let mut table = OpTable::new(tv.iter().cloned().collect());
+    let rows = field(&doc, "matrix")?.as_array().ok_or("'matrix' should be an array")?;
+    if rows.len() != tv.len() {
+        return Err(format!("'matrix' should have {} rows", tv.len()));
+    }
+    for (a, row) in tv.iter().zip(rows) {
+        let row = row.as_array().filter(|r| r.len() == tv.len());
+        let row = row.ok_or(format!("the row of '{}' should have {} cells", a, tv.len()))?;
+        for (b, cell) in tv.iter().zip(row) {
+            if let Some(c) = relation_char(cell, "matrix")? {
+                table.insert(&(a.clone(), b.clone()), c);
+            }
+        }
+    }
+    let conflicts = match doc.get("conflicts") {
+        Some(conflicts) => conflicts.as_array().ok_or("'conflicts' should be an array")?,
+        None => &[],
+    };
+    for c in conflicts {
+        let pair = (
+            field(c, "left")?.as_str().ok_or("'left' should be a string")?.to_string(),
+            field(c, "right")?.as_str().ok_or("'right' should be a string")?.to_string(),
+        );
+        if !tv.contains(&pair.0) || !tv.contains(&pair.1) {
+            return Err(format!("the conflict between '{}' and '{}' is not in 'terminals'", pair.0, pair.1));
+        }
+        let kept = relation_char(field(c, "kept")?, "kept")?.ok_or("'kept' should not be null")?;
+        let rejected = relation_char(field(c, "rejected")?, "rejected")?.ok_or("'rejected' should not be null")?;
+        if table.get(&pair.0, &pair.1) != Some(kept) {
+            return Err(format!("the conflict between '{}' and '{}' does not keep the matrix", pair.0, pair.1));
+        }
+        table.insert(&pair, rejected);
+    }
+    Ok((productions, table))
+}
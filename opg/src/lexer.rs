@@ -0,0 +1,144 @@
+//! # lexer
+//!
+//! `lexer` splits an input sentence
+//! into the terminals of a grammar.
+
+use std::collections::HashSet;
+
+///
+/// A struct to represent a token
+/// read from the input.
+///
+#[derive(Clone)]
+pub struct Token {
+    /// the terminal of the grammar.
+    pub kind: String,
+    /// the text in the input.
+    pub text: String,
+}
+
+impl Token {
+    ///
+    /// Create a token whose text
+    /// is the terminal itself.
+    ///
+    pub fn new(kind: &str) -> Token {
+        Token {
+            kind: kind.to_string(),
+            text: kind.to_string(),
+        }
+    }
+}
+
+///
+/// Find the terminal standing for
+/// operands, such as `i` or `id`.
+///
+/// ## Principles
+/// The word-like terminals are candidates.
+/// Prefer `num`, `id` and `i` if there are several,
+/// otherwise take the smallest one.
+///
+pub fn operand_terminal(ts: &HashSet<String>) -> Option<String> {
+    let mut words: Vec<&String> = ts
+        .iter()
+        .filter(|t| t.chars().all(|c| c.is_alphanumeric() || c == '_'))
+        .collect();
+    words.sort();
+    for preferred in ["num", "id", "i"].iter() {
+        if words.iter().any(|w| w == preferred) {
+            return Some(preferred.to_string());
+        }
+    }
+    words.first().map(|w| w.to_string())
+}
+
+///
+/// Tokenize the input.
+///
+/// ## Input
+/// `input` the sentence to be split.
+///
+/// `ts` the terminal set of the grammar.
+///
+/// ## Return
+/// The tokens ended with `$`.
+///
+/// ## Example
+/// ```
+/// let tokens = lexer::tokenize("1 + 2 * 3", table.terminals())?;
+/// ```
+///
+/// ## Principles
+/// - Numeric literals and identifiers which are not
+///   terminals become the operand terminal given by
+///   `operand_terminal()`, keeping the text.
+/// - Other characters are matched with the longest
+///   terminal starting from the position.
+///
+pub fn tokenize(input: &str, ts: &HashSet<String>) -> Result<Vec<Token>, String> {
+    let operand = operand_terminal(ts);
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut pos = 0;
+    while pos < chars.len() {
+        let c = chars[pos];
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        if c.is_ascii_digit() || (c == '.' && chars.get(pos + 1).is_some_and(|d| d.is_ascii_digit())) {
+            // numeric literal: digits, fraction and exponent
+            while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+                pos += 1;
+            }
+            if pos < chars.len() && (chars[pos] == 'e' || chars[pos] == 'E') {
+                let mut exp = pos + 1;
+                if exp < chars.len() && (chars[exp] == '+' || chars[exp] == '-') {
+                    exp += 1;
+                }
+                if exp < chars.len() && chars[exp].is_ascii_digit() {
+                    pos = exp;
+                    while pos < chars.len() && chars[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            let word: String = chars[start..pos].iter().collect();
+            if ts.contains(&word) {
+                tokens.push(Token::new(&word));
+                continue;
+            }
+        } else {
+            // the longest terminal at the position
+            let rest: String = chars[pos..].iter().collect();
+            let matched = ts
+                .iter()
+                .filter(|t| t.as_str() != "$" && rest.starts_with(t.as_str()))
+                .max_by_key(|t| t.len());
+            match matched {
+                Some(t) => {
+                    pos += t.chars().count();
+                    tokens.push(Token::new(t));
+                    continue;
+                }
+                None => return Err(format!("unexpected character '{}' at {}", c, start)),
+            }
+        }
+        let text: String = chars[start..pos].iter().collect();
+        match &operand {
+            Some(kind) => tokens.push(Token {
+                kind: kind.clone(),
+                text,
+            }),
+            None => return Err(format!("no operand terminal for '{}'", text)),
+        }
+    }
+    tokens.push(Token::new("$"));
+    Ok(tokens)
+}
@@ -0,0 +1,813 @@
+//! # Operator Precedence Grammar Parser
+//!
+//! `opg` reads an context-free grammar input 
+//! and outputs the precedence of the operators.
+
+pub mod antlr;
+pub mod binary;
+pub mod bison;
+pub mod build;
+pub mod check;
+pub mod cnf;
+pub mod codegen;
+pub mod compress;
+pub mod cyk;
+pub mod derivation;
+mod dfs;
+pub mod earley;
+pub mod grammar;
+pub mod hygiene;
+mod interop;
+pub mod json;
+pub mod lexer;
+pub mod ll1;
+pub mod lr;
+pub mod parser;
+pub mod pratt;
+pub mod quad;
+pub mod semantic;
+pub mod sentence;
+pub mod simple;
+pub mod table;
+pub mod transform;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+
+///
+/// A struct to 
+/// represent a production.
+///
+#[derive(Clone)]
+pub struct Production {
+    /// the left side of the production.
+    pub left: String,
+    /// the right side of the production.
+    pub right: Vec<String>,
+    /// the semantic action written in
+    /// braces after the right side.
+    pub action: Option<String>,
+    /// the quadruple templates written
+    /// as `@( op, arg1, arg2, result )`.
+    pub templates: Vec<String>,
+}
+
+impl Production {
+    ///
+    /// Create a production without
+    /// action and templates.
+    ///
+    pub fn new(left: &str, right: Vec<String>) -> Production {
+        Production {
+            left: left.to_string(),
+            right,
+            action: None,
+            templates: Vec::new(),
+        }
+    }
+}
+
+impl Display for Production {
+    ///
+    /// Output the production like `E -> E + T`,
+    /// and the empty right side as `ε`.
+    ///
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.right.is_empty() {
+            write!(f, "{} -> {}", self.left, EPSILON)
+        } else {
+            write!(f, "{} -> {}", self.left, self.right.join(" "))
+        }
+    }
+}
+
+/// The symbol of the empty string in the grammar file.
+pub const EPSILON: &str = "ε";
+
+///
+/// The associativity of an operator declared
+/// by `%left`, `%right` or `%nonassoc`.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Assoc {
+    Left,
+    Right,
+    Nonassoc,
+}
+
+///
+/// Generate FIRSTVT set for
+/// every non-terminals.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated 
+/// from the function `get_non_terminals()`. 
+///
+/// ## Return
+/// `firstvt` the set of FIRSTVT
+/// for all terminals.
+///
+/// ## Example
+/// ```
+///  let firstvt:HashMap<String, HashSet<String>> = gen_firstvt(&productions, &nts);
+/// ```
+///
+/// ## Principles
+/// Go through every production
+/// and search the following patterns:
+/// 1. U => U_1y
+/// 2. U => U_1Ty
+/// 3. U => Ty
+///
+/// call `dfs::compose_element()` 
+/// to get the final result.
+///
+pub fn gen_firstvt(
+    productions: &[Production],
+    nts: &HashSet<String>,
+) -> HashMap<String, HashSet<String>> {
+    // every non-terminal needs an entry for the DFS
+    let mut firstvtmono: HashMap<String, HashSet<String>> =
+        nts.iter().map(|nt| (nt.clone(), HashSet::new())).collect();
+    let mut firstvtcon: HashMap<String, HashSet<String>> = HashMap::new();
+
+    // Find mono terminal and
+    // record the containing part
+    for p in productions {
+        if p.right.is_empty() {
+            continue;
+        }
+        if nts.contains(p.right.first().unwrap()) {
+            // Case 1: U => U_1y
+            let ntc = firstvtcon
+                .entry(p.left.to_string())
+                .or_default();
+            ntc.insert(p.right.first().unwrap().to_string());
+            // Case 1*: U => U_1Ty
+            if p.right.len() > 1 && !nts.contains(&p.right[1]) {
+                let vts = firstvtmono
+                    .entry(p.left.to_string())
+                    .or_default();
+                vts.insert(p.right[1].clone());
+            }
+        } else {
+            // Case 2: U => Ty
+            let vts = firstvtmono
+                .entry(p.left.to_string())
+                .or_default();
+            vts.insert(p.right.first().unwrap().to_string());
+        }
+    }
+    dfs::compose_elements(&firstvtmono, &firstvtcon)
+}
+
+///
+/// Generate LASTVT set for
+/// every non-terminals.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated 
+/// from the function `get_non_terminals()`. 
+///
+/// ## Return
+/// `lastvt` the set of LASTVT
+/// for all terminals.
+///
+/// ## Example
+/// ```
+///  let lastvt:HashMap<String, HashSet<String>> = gen_lastvt(&productions, &nts);
+/// ```
+///
+/// ## Principles
+/// Go through every production
+/// and search the following patterns:
+/// 1. U => xU_1
+/// 2. U => xTU_1
+/// 3. U => xT
+///
+/// call `dfs::compose_element()` 
+/// to get the final result.
+///
+pub fn gen_lastvt(
+    productions: &[Production],
+    nts: &HashSet<String>,
+) -> HashMap<String, HashSet<String>> {
+    // every non-terminal needs an entry for the DFS
+    let mut lastvtmono: HashMap<String, HashSet<String>> =
+        nts.iter().map(|nt| (nt.clone(), HashSet::new())).collect();
+    let mut lastvtcon: HashMap<String, HashSet<String>> = HashMap::new();
+
+    // Find mono terminal and
+    // record the containing part
+    for p in productions {
+        if p.right.is_empty() {
+            continue;
+        }
+        if nts.contains(p.right.last().unwrap()) {
+            // Case 1: U => xU_1
+            let ntc = lastvtcon
+                .entry(p.left.to_string())
+                .or_default();
+            ntc.insert(p.right.last().unwrap().to_string());
+            // Case 1*: U => xTU_1
+            if p.right.len() > 1 && !nts.contains(&p.right[p.right.len() - 2]) {
+                let vts = lastvtmono
+                    .entry(p.left.to_string())
+                    .or_default();
+                vts.insert(p.right[p.right.len() - 2].clone());
+            }
+        } else {
+            // Case 2: U => xT
+            let vts = lastvtmono
+                .entry(p.left.to_string())
+                .or_default();
+            vts.insert(p.right.last().unwrap().to_string());
+        }
+    }
+    dfs::compose_elements(&lastvtmono, &lastvtcon)
+}
+
+///
+/// Find the equal operators
+///
+/// ## Input
+/// `table` the mutable `OpTable` struct for output
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated 
+/// from the function `get_non_terminals()`. 
+///
+/// ## Example
+/// ```
+/// find_eq(&mut table, &productions, &nts);
+/// ```
+///
+/// ## Principles
+/// Search the pattern of ..T1..T2..
+/// and make T1=T2, notice that it is
+/// NOT indicate that T2=T1.
+/// 
+pub fn find_eq(
+    table: &mut table::OpTable,
+    productions: &[Production],
+    nts: &HashSet<String>,
+) {
+    for p in productions {
+        // Get all terminals in the right side
+        let mut pe = p.right.clone();
+        pe.retain(|x| !nts.contains(x));
+        // Since the relation is not commutative
+        // Equal will be assigned from left to right.
+        for i in 0..pe.len() {
+            for j in i + 1..pe.len() {
+                table.insert(&(pe[i].clone(), pe[j].clone()), '=');
+            }
+        }
+    }
+}
+
+///
+/// Find the less relations
+///
+/// ## Input
+/// `table` the mutable `OpTable` struct for output
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated 
+/// from the function `get_non_terminals()`.
+///
+/// `firstvt` the FIRSTVT set generated from the function
+/// `gen_firstvt()`. 
+///
+/// ## Example
+/// ```
+/// let firstvt = gen_firstvt(&productions, &nts);
+/// find_less(&mut table, &productions, &nts, &firstvt);
+/// ```
+///
+/// ## Principles
+/// Find ...T1U1..., where T2 is in FIRSTVT(U1) and
+/// make T1<T2. Notice that this doesn't 
+/// indicate that T2>T1.
+/// 
+pub fn find_less(
+    table: &mut table::OpTable,
+    productions: &[Production],
+    nts: &HashSet<String>,
+    firstvt: &HashMap<String, HashSet<String>>,
+) {
+    for p in productions {
+        if p.right.len() <= 1 {
+            continue;
+        }
+        for i in 0..p.right.len() - 1 {
+            if !nts.contains(&p.right[i]) && nts.contains(&p.right[i + 1]) {
+                for t in firstvt[&p.right[i + 1]].iter() {
+                    table.insert(&(p.right[i].to_owned(), t.to_owned()), '<');
+                }
+            }
+        }
+    }
+}
+
+///
+/// Find the greater relations
+///
+/// ## Input
+/// `table` the mutable `OpTable` struct for output
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated 
+/// from the function `get_non_terminals()`.
+///
+/// `lastvt` the LASTVT set generated from the function
+/// `gen_lastvt()`. 
+///
+/// ## Example
+/// ```
+/// let lastvt = gen_firstvt(&productions, &nts);
+/// find_greater(&mut table, &productions, &nts, &lastvt);
+/// ```
+///
+/// ## Principles
+/// Find ...U1T2..., where T1 is in LASTVT(U1) and
+/// make T1>T2. Notice that this doesn't 
+/// indicate that T2<T1.
+/// 
+pub fn find_greater(
+    table: &mut table::OpTable,
+    productions: &[Production],
+    nts: &HashSet<String>,
+    lastvt: &HashMap<String, HashSet<String>>,
+) {
+    for p in productions {
+        if p.right.len() <= 1 {
+            continue;
+        }
+        for i in 0..p.right.len() - 1 {
+            if nts.contains(&p.right[i]) && !nts.contains(&p.right[i + 1]) {
+                for t in lastvt[&p.right[i]].iter() {
+                    table.insert( &(t.to_owned(), p.right[i + 1].to_owned()), '>');
+                }
+            }
+        }
+    }
+}
+
+///
+/// Check the lines of the grammar contents
+/// before `gen_productions()`.
+///
+/// ## Input
+/// `contents` the string read from file.
+///
+/// ## Output
+/// The error with the line number, if a line of the
+/// productions has no `->` or no single non-terminal before it.
+///
+/// ## Example
+/// ```
+/// check_grammar(&contents)?;
+/// let productions: Vec<Production> = gen_productions(&contents);
+/// ```
+///
+/// ## Principle
+/// `gen_productions()` expects `->` in every non-empty
+/// line except the directives starting with "%",
+/// with a single symbol on the left.
+///
+pub fn check_grammar(contents: &str) -> Result<(), String> {
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+        match line.split_once("->") {
+            Some((left, _)) if !left.trim().is_empty() && !left.trim().contains(char::is_whitespace) => {}
+            Some(_) => return Err(format!("line {}: expected a non-terminal before '->'", i + 1)),
+            None => return Err(format!("line {}: expected '->'", i + 1)),
+        }
+    }
+    Ok(())
+}
+
+///
+/// Generate production list for
+/// the grammar contents.
+///
+/// ## Input
+/// `contents` the string read from file.
+///
+/// ## Output
+/// `p` the vector of productions.
+///
+/// ## Example
+/// ```
+/// let mut productions: Vec<Production> = gen_productions(&contents);
+/// ```
+///
+/// ## Principle
+/// For every non-empty line in the file, except the
+/// directives starting with "%", split it on "->".
+/// Then split the trimmed right side based on "|"
+/// outside of the semantic parts, and take the
+/// `{ ... }` of each alternative as its semantic action
+/// and the `@( ... )` as its quadruple templates.
+/// An empty alternative or a single `ε` is the empty string.
+/// After processing, push the new `Production` struct
+/// into the result.
+///
+pub fn gen_productions(contents: &str) -> Vec<Production> {
+    let mut p: Vec<Production> = Vec::new();
+    for line in contents.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('%') {
+            continue;
+        }
+        let ps: Vec<_> = line.splitn(2, "->").collect();
+        let ls = ps[0].trim();
+        for rsp in split_alternatives(ps[1]) {
+            let (body, action, templates) = split_semantics(rsp);
+            let mut right: Vec<String> = body.split_whitespace().map(|s| s.to_string()).collect();
+            if right.len() == 1 && right[0] == EPSILON {
+                right.clear();
+            }
+            p.push(Production {
+                left: ls.to_string(),
+                right,
+                action,
+                templates,
+            });
+        }
+    }
+    p
+}
+
+///
+/// Format the productions in the
+/// syntax of the grammar file.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Output
+/// One line for every non-terminal in the order of
+/// appearance, with the alternatives split by "|".
+///
+/// ## Example
+/// ```
+/// print!("{}", format_grammar(&productions));
+/// ```
+///
+/// ## Principle
+/// The output could be read back by `gen_productions()`.
+///
+pub fn format_grammar(productions: &[Production]) -> String {
+    let mut lefts: Vec<&String> = Vec::new();
+    for p in productions {
+        if !lefts.contains(&&p.left) {
+            lefts.push(&p.left);
+        }
+    }
+    let mut output = String::new();
+    for left in lefts {
+        let alts: Vec<String> = productions
+            .iter()
+            .filter(|p| p.left == *left)
+            .map(|p| {
+                let mut alt = if p.right.is_empty() {
+                    EPSILON.to_string()
+                } else {
+                    p.right.join(" ")
+                };
+                if let Some(action) = &p.action {
+                    alt += &format!(" {{ {} }}", action);
+                }
+                for template in p.templates.iter() {
+                    alt += &format!(" @( {} )", template);
+                }
+                alt
+            })
+            .collect();
+        output += &format!("{} -> {}\n", left, alts.join(" | "));
+    }
+    output
+}
+
+///
+/// Generate the operator precedence
+/// declared in the grammar contents.
+///
+/// ## Input
+/// `contents` the string read from file.
+///
+/// ## Output
+/// The level and the associativity of every operator.
+///
+/// ## Example
+/// ```
+/// // %left + -
+/// // %left * /
+/// let prec = gen_precedence(&contents);
+/// ```
+///
+/// ## Principle
+/// Every `%left`, `%right` or `%nonassoc` line starts a new
+/// level, and the later line has the higher precedence
+/// as in yacc.
+///
+pub fn gen_precedence(contents: &str) -> HashMap<String, (usize, Assoc)> {
+    let mut prec: HashMap<String, (usize, Assoc)> = HashMap::new();
+    let mut level = 0;
+    for line in contents.lines() {
+        let mut vs = line.split_whitespace();
+        let assoc = match vs.next() {
+            Some("%left") => Assoc::Left,
+            Some("%right") => Assoc::Right,
+            Some("%nonassoc") => Assoc::Nonassoc,
+            _ => continue,
+        };
+        level += 1;
+        for v in vs {
+            prec.insert(v.to_string(), (level, assoc));
+        }
+    }
+    prec
+}
+
+///
+/// Generate the weights of the productions
+/// declared in the grammar contents.
+///
+/// ## Input
+/// `contents` the string read from file.
+///
+/// `productions` the vector of struct `Production`
+///
+/// ## Output
+/// The weight of every production, 1 by default.
+///
+/// ## Example
+/// ```
+/// // %weight 3 E -> E + T | T
+/// let weights = gen_weights(&contents, &productions);
+/// ```
+///
+/// ## Principle
+/// A `%weight n` line is followed by the productions
+/// in the grammar syntax, which are matched by the
+/// left and right sides.
+///
+pub fn gen_weights(contents: &str, productions: &[Production]) -> Vec<u32> {
+    let mut weights: Vec<u32> = vec![1; productions.len()];
+    for line in contents.lines() {
+        let rest = match line.trim_start().strip_prefix("%weight") {
+            Some(rest) => rest.trim_start(),
+            None => continue,
+        };
+        let (w, rest) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+        let w: u32 = match w.parse() {
+            Ok(w) => w,
+            Err(_) => continue,
+        };
+        if !rest.contains("->") {
+            continue;
+        }
+        for q in gen_productions(rest) {
+            for (i, p) in productions.iter().enumerate() {
+                if p.left == q.left && p.right == q.right {
+                    weights[i] = w;
+                }
+            }
+        }
+    }
+    weights
+}
+
+///
+/// Split the right side of a line into
+/// alternatives on the "|" that are not
+/// enclosed by an action or a template.
+///
+fn split_alternatives(rs: &str) -> Vec<&str> {
+    let mut alts: Vec<&str> = Vec::new();
+    // the closers expected by the open actions and templates
+    let mut closers: Vec<char> = Vec::new();
+    let mut start = 0;
+    let mut prev = ' ';
+    for (pos, ch) in rs.char_indices() {
+        match ch {
+            '{' => closers.push('}'),
+            '(' if prev == '@' => closers.push(')'),
+            '}' | ')' if closers.last() == Some(&ch) => {
+                closers.pop();
+            }
+            '|' if closers.is_empty() => {
+                alts.push(&rs[start..pos]);
+                start = pos + 1;
+            }
+            _ => {}
+        }
+        prev = ch;
+    }
+    alts.push(&rs[start..]);
+    alts
+}
+
+///
+/// Split an alternative into the symbols,
+/// the action in `{ ... }` and the
+/// templates in `@( ... )`.
+///
+fn split_semantics(alt: &str) -> (String, Option<String>, Vec<String>) {
+    let mut body = String::new();
+    let mut action: Option<String> = None;
+    let mut templates: Vec<String> = Vec::new();
+    let mut rest = alt;
+    loop {
+        let brace = rest.find('{');
+        let at = rest.find("@(");
+        let (start, open, close) = match (brace, at) {
+            (Some(b), Some(a)) if a < b => (a, 2, ')'),
+            (Some(b), _) => (b, 1, '}'),
+            (None, Some(a)) => (a, 2, ')'),
+            (None, None) => break,
+        };
+        let end = match rest[start..].find(close) {
+            Some(e) => start + e,
+            None => break,
+        };
+        body.push_str(&rest[..start]);
+        body.push(' ');
+        let inner = rest[start + open..end].trim().to_string();
+        if close == '}' {
+            action = Some(inner);
+        } else {
+            templates.push(inner);
+        }
+        rest = &rest[end + 1..];
+    }
+    body.push_str(rest);
+    (body, action, templates)
+}
+
+///
+/// Get all the non terminals from
+/// the generated production.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Output
+/// The hashset contains non-terminals.
+/// 
+/// ## Example
+/// ```
+/// let nts = get_non_terminals(&productions);
+/// ```
+///
+/// ## Principles
+/// Collect all the symbol on the left side
+/// in the productions.
+///
+pub fn get_non_terminals(productions: &[Production]) -> HashSet<String> {
+    productions.iter().map(|s| s.left.clone()).collect()
+}
+
+///
+/// Get a new symbol from `name`
+/// by appending `'` until it is not used.
+///
+/// ## Example
+/// ```
+/// let e1 = fresh_symbol("E'", &used);
+/// ```
+///
+pub fn fresh_symbol(name: &str, used: &HashSet<String>) -> String {
+    let mut name = name.to_string();
+    while used.contains(&name) {
+        name.push('\'');
+    }
+    name
+}
+
+///
+/// Get terminals
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated 
+/// from the function `get_non_terminals()`. 
+///
+/// ## Output
+/// The hashset contains terminals.
+/// 
+/// ## Example
+/// ```
+/// let nts = get_non_terminals(&productions);
+/// let ts = get_terminals(&productions, &nts);
+/// ```
+///
+/// ## Principles
+/// To avoid repetative computing, receive the pre-computed
+/// non-terminal set and eliminate them among the candidates
+/// on the right side in each production.
+///
+pub fn get_terminals(productions: &[Production], nts: &HashSet<String>) -> HashSet<String> {
+    let mut ts: HashSet<String> = HashSet::new();
+    for p in productions {
+        for v in p.right.iter() {
+            if !nts.contains(v) {
+                ts.insert(v.clone());
+            }
+        }
+    }
+    ts
+}
+
+///
+/// Get the symbols in the order
+/// of their first appearance.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated 
+/// from the function `get_non_terminals()`. 
+///
+/// ## Output
+/// The non-terminals and the terminals as vectors,
+/// the starting non-terminal comes first.
+///
+/// ## Example
+/// ```
+/// let (ntv, tv) = ordered_symbols(&productions, &nts);
+/// ```
+///
+pub fn ordered_symbols(productions: &[Production], nts: &HashSet<String>) -> (Vec<String>, Vec<String>) {
+    let mut ntv: Vec<String> = Vec::new();
+    let mut tv: Vec<String> = Vec::new();
+    for p in productions {
+        for v in std::iter::once(&p.left).chain(p.right.iter()) {
+            let list = if nts.contains(v) { &mut ntv } else { &mut tv };
+            if !list.contains(v) {
+                list.push(v.clone());
+            }
+        }
+    }
+    (ntv, tv)
+}
+
+///
+/// Build the Operator Precedence Table
+/// for the productions.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Return
+/// The `OpTable` of the grammar.
+///
+/// ## Example
+/// ```
+/// let table = gen_table(&productions);
+/// ```
+///
+/// ## Principles
+/// Generate FIRSTVT and LASTVT for the productions.
+/// Then add S->$S$ for the starting non-terminal
+/// on a copy of the productions.
+/// Generate `OpTable` struct based on the algorithm
+/// of `find_eq()`, `find_less()`, `find_greater()`.
+///
+pub fn gen_table(productions: &[Production]) -> table::OpTable {
+    let mut productions = productions.to_vec();
+    let nts = get_non_terminals(&productions);
+    let firstvt = gen_firstvt(&productions, &nts);
+    let lastvt = gen_lastvt(&productions, &nts);
+
+    // add the $S$ for the starting non-terminal
+    let startnt = productions[0].left.clone();
+    productions.push(Production::new(
+        &startnt,
+        vec!["$".to_string(), startnt.clone(), "$".to_string()],
+    ));
+
+    let ts = get_terminals(&productions, &nts);
+    let mut table = table::OpTable::new(ts);
+
+    // if there is conflict on operator precedence,
+    // then the grammar is ambiguous.
+    find_eq(&mut table, &productions, &nts);
+    find_less(&mut table, &productions, &nts, &firstvt);
+    find_greater(&mut table, &productions, &nts, &lastvt);
+    table
+}
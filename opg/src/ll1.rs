@@ -0,0 +1,262 @@
+//! # ll1
+//!
+//! `ll1` provides nullable, FIRST and FOLLOW sets
+//! and the LL(1) predictive parsing table,
+//! as a sibling of the FIRSTVT and LASTVT analysis.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::table;
+use crate::Production;
+
+///
+/// Generate the nullable non-terminals.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Return
+/// The non-terminals which derive the empty string.
+///
+/// ## Principles
+/// A non-terminal is nullable if a production of it
+/// has only nullable symbols on the right side.
+/// Repeat until nothing changes.
+///
+pub fn gen_nullable(productions: &[Production]) -> HashSet<String> {
+    let mut nullable: HashSet<String> = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for p in productions {
+            if !nullable.contains(&p.left) && p.right.iter().all(|v| nullable.contains(v)) {
+                nullable.insert(p.left.clone());
+                changed = true;
+            }
+        }
+    }
+    nullable
+}
+
+///
+/// Compute FIRST of a sequence of symbols.
+///
+/// ## Return
+/// The terminals which could begin the sequence,
+/// and whether the whole sequence is nullable.
+///
+/// ## Example
+/// ```
+/// let (fs, empty) = ll1::first_of(&p.right, &nts, &nullable, &first);
+/// ```
+///
+pub fn first_of(
+    seq: &[String],
+    nts: &HashSet<String>,
+    nullable: &HashSet<String>,
+    first: &HashMap<String, HashSet<String>>,
+) -> (HashSet<String>, bool) {
+    let mut fs: HashSet<String> = HashSet::new();
+    for v in seq {
+        if !nts.contains(v) {
+            fs.insert(v.clone());
+            return (fs, false);
+        }
+        if let Some(f) = first.get(v) {
+            fs.extend(f.iter().cloned());
+        }
+        if !nullable.contains(v) {
+            return (fs, false);
+        }
+    }
+    (fs, true)
+}
+
+///
+/// Generate FIRST set for
+/// every non-terminals.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `nullable` the set generated from `gen_nullable()`.
+///
+/// ## Return
+/// The terminals for each non-terminal, without ε.
+///
+/// ## Principles
+/// For every `U -> X1 X2 ... Xn`, add FIRST(X1 X2 ... Xn)
+/// to FIRST(U). Repeat until nothing changes.
+///
+pub fn gen_first(
+    productions: &[Production],
+    nts: &HashSet<String>,
+    nullable: &HashSet<String>,
+) -> HashMap<String, HashSet<String>> {
+    let mut first: HashMap<String, HashSet<String>> =
+        nts.iter().map(|nt| (nt.clone(), HashSet::new())).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for p in productions {
+            let (fs, _) = first_of(&p.right, nts, nullable, &first);
+            let set = first.get_mut(&p.left).unwrap();
+            for t in fs {
+                changed |= set.insert(t);
+            }
+        }
+    }
+    first
+}
+
+///
+/// Generate FOLLOW set for
+/// every non-terminals.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `nullable` the set generated from `gen_nullable()`.
+///
+/// `first` the set generated from `gen_first()`.
+///
+/// ## Principles
+/// `$` follows the starting non-terminal.
+/// For every `U -> xBy`, add FIRST(y) to FOLLOW(B),
+/// and add FOLLOW(U) to FOLLOW(B) if y is nullable.
+/// Repeat until nothing changes.
+///
+pub fn gen_follow(
+    productions: &[Production],
+    nts: &HashSet<String>,
+    nullable: &HashSet<String>,
+    first: &HashMap<String, HashSet<String>>,
+) -> HashMap<String, HashSet<String>> {
+    let mut follow: HashMap<String, HashSet<String>> =
+        nts.iter().map(|nt| (nt.clone(), HashSet::new())).collect();
+    if let Some(p) = productions.first() {
+        follow.get_mut(&p.left).unwrap().insert("$".to_string());
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for p in productions {
+            for (i, v) in p.right.iter().enumerate() {
+                if !nts.contains(v) {
+                    continue;
+                }
+                let (mut fs, empty) = first_of(&p.right[i + 1..], nts, nullable, first);
+                if empty {
+                    fs.extend(follow[&p.left].iter().cloned());
+                }
+                let set = follow.get_mut(v).unwrap();
+                for t in fs {
+                    changed |= set.insert(t);
+                }
+            }
+        }
+    }
+    follow
+}
+
+///
+/// Format a set in the given order, like `{ (, i }`.
+///
+/// The symbols not in `order` are put at the end.
+///
+pub fn format_set(order: &[String], set: &HashSet<String>) -> String {
+    let mut items: Vec<&String> = order.iter().filter(|v| set.contains(*v)).collect();
+    let mut rest: Vec<&String> = set.iter().filter(|v| !order.contains(v)).collect();
+    rest.sort();
+    items.extend(rest);
+    let items: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
+    format!("{{ {} }}", items.join(", "))
+}
+
+///
+/// A struct of LL(1) predictive parsing table.
+///
+/// Every cell keeps the indices of the productions,
+/// more than one index means a conflict.
+///
+pub struct Ll1Table {
+    table: HashMap<(String, String), Vec<usize>>,
+}
+
+impl Ll1Table {
+    ///
+    /// Build the table.
+    ///
+    /// ## Principles
+    /// For every `U -> x`, put it into M[U, a] for every
+    /// `a` in FIRST(x), and into M[U, b] for every
+    /// `b` in FOLLOW(U) if x is nullable.
+    ///
+    pub fn new(
+        productions: &[Production],
+        nts: &HashSet<String>,
+        nullable: &HashSet<String>,
+        first: &HashMap<String, HashSet<String>>,
+        follow: &HashMap<String, HashSet<String>>,
+    ) -> Ll1Table {
+        let mut table: HashMap<(String, String), Vec<usize>> = HashMap::new();
+        for (i, p) in productions.iter().enumerate() {
+            let (mut fs, empty) = first_of(&p.right, nts, nullable, first);
+            if empty {
+                fs.extend(follow[&p.left].iter().cloned());
+            }
+            for t in fs {
+                let cell = table.entry((p.left.clone(), t)).or_default();
+                if !cell.contains(&i) {
+                    cell.push(i);
+                }
+            }
+        }
+        Ll1Table { table }
+    }
+
+    ///
+    /// The productions in M[`nt`, `t`].
+    ///
+    pub fn get(&self, nt: &str, t: &str) -> &[usize] {
+        self.table
+            .get(&(nt.to_string(), t.to_string()))
+            .map_or(&[], |v| v.as_slice())
+    }
+
+    ///
+    /// Report the cells with more than one production.
+    ///
+    pub fn conflicts(&self, productions: &[Production]) -> Vec<String> {
+        let mut keys: Vec<&(String, String)> =
+            self.table.keys().filter(|k| self.table[*k].len() > 1).collect();
+        keys.sort();
+        keys.iter()
+            .map(|k| {
+                let ps: Vec<String> = self.table[*k].iter().map(|i| productions[*i].to_string()).collect();
+                format!("LL(1) conflict at M[{}, {}]: {}", k.0, k.1, ps.join(" | "))
+            })
+            .collect()
+    }
+
+    ///
+    /// Render the table in the format of `OpTable`.
+    ///
+    pub fn render(&self, productions: &[Production], ntv: &[String], tv: &[String]) -> String {
+        table::render(ntv, tv, |i, j| {
+            let ps: Vec<String> = self.get(i, j).iter().map(|k| productions[*k].to_string()).collect();
+            if ps.is_empty() {
+                " ".to_string()
+            } else {
+                ps.join(" / ")
+            }
+        })
+    }
+}
@@ -0,0 +1,636 @@
+//! # lr
+//!
+//! `lr` provides the LR(0) and LR(1) item sets,
+//! the canonical collection with GOTO transitions,
+//! the LALR(1) merging of cores and the
+//! LR(0), SLR(1), LR(1) and LALR(1) ACTION/GOTO tables.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+
+use crate::ll1;
+use crate::table;
+use crate::{Assoc, Production};
+
+///
+/// Augment the grammar with `S' -> S`.
+///
+/// ## Return
+/// The productions with `S' -> S` at index 0.
+/// The new starting non-terminal gets more `'`
+/// if `S'` is already in the grammar.
+///
+pub fn augment(productions: &[Production]) -> Vec<Production> {
+    let start = productions[0].left.clone();
+    let mut left = format!("{}'", start);
+    while productions.iter().any(|p| p.left == left) {
+        left.push('\'');
+    }
+    let mut augmented = vec![Production::new(&left, vec![start])];
+    augmented.extend(productions.iter().cloned());
+    augmented
+}
+
+///
+/// An item, i.e. a production with a dot,
+/// and the lookahead for LR(1).
+///
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Item {
+    /// the index of the production.
+    pub prod: usize,
+    /// the position of the dot in the right side.
+    pub dot: usize,
+    /// the lookahead terminal, `None` for LR(0).
+    pub la: Option<String>,
+}
+
+impl Item {
+    ///
+    /// The symbol after the dot, if any.
+    ///
+    pub fn next<'a>(&self, productions: &'a [Production]) -> Option<&'a String> {
+        productions[self.prod].right.get(self.dot)
+    }
+
+    ///
+    /// The item with the dot moved forward.
+    ///
+    fn advance(&self) -> Item {
+        Item {
+            prod: self.prod,
+            dot: self.dot + 1,
+            la: self.la.clone(),
+        }
+    }
+
+    ///
+    /// The core of the item, without the lookahead.
+    ///
+    pub fn core(&self) -> (usize, usize) {
+        (self.prod, self.dot)
+    }
+
+    ///
+    /// Output the item like `E -> E . + T`,
+    /// without the lookahead.
+    ///
+    pub fn format(&self, productions: &[Production]) -> String {
+        let p = &productions[self.prod];
+        let mut symbols: Vec<&str> = p.right.iter().map(|s| s.as_str()).collect();
+        symbols.insert(self.dot, ".");
+        format!("{} -> {}", p.left, symbols.join(" "))
+    }
+}
+
+///
+/// The canonical collection of item sets
+/// with the GOTO transitions.
+///
+pub struct Automaton {
+    /// the item sets, including the closure.
+    pub states: Vec<Vec<Item>>,
+    /// the transitions between the item sets.
+    pub goto: HashMap<(usize, String), usize>,
+    /// the symbols of the transitions in order.
+    pub symbols: Vec<String>,
+}
+
+///
+/// Compute the LR(0) closure of the kernel items.
+///
+/// ## Principles
+/// For every item `A -> x . B y`, add `B -> . z`
+/// for every production of `B`. Repeat until
+/// nothing is added.
+///
+pub fn closure(kernel: &[Item], productions: &[Production], nts: &HashSet<String>) -> Vec<Item> {
+    let mut items: Vec<Item> = kernel.to_vec();
+    let mut i = 0;
+    while i < items.len() {
+        if let Some(b) = items[i].next(productions) {
+            if nts.contains(b) {
+                for (k, p) in productions.iter().enumerate() {
+                    let item = Item {
+                        prod: k,
+                        dot: 0,
+                        la: None,
+                    };
+                    if p.left == *b && !items.contains(&item) {
+                        items.push(item);
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    items
+}
+
+///
+/// Compute the LR(1) closure of the kernel items.
+///
+/// ## Principles
+/// For every item `[A -> x . B y, a]`, add `[B -> . z, b]`
+/// for every production of `B` and every `b` in FIRST(ya).
+/// Repeat until nothing is added.
+///
+pub fn closure1(
+    kernel: &[Item],
+    productions: &[Production],
+    nts: &HashSet<String>,
+    nullable: &HashSet<String>,
+    first: &HashMap<String, HashSet<String>>,
+) -> Vec<Item> {
+    let mut items: Vec<Item> = kernel.to_vec();
+    let mut i = 0;
+    while i < items.len() {
+        let item = items[i].clone();
+        if let Some(b) = item.next(productions).filter(|b| nts.contains(*b)) {
+            let rest = &productions[item.prod].right[item.dot + 1..];
+            let (fs, empty) = ll1::first_of(rest, nts, nullable, first);
+            let mut las: Vec<String> = fs.into_iter().collect();
+            las.sort();
+            if empty {
+                las.extend(item.la.iter().cloned());
+            }
+            for (k, p) in productions.iter().enumerate() {
+                if p.left != *b {
+                    continue;
+                }
+                for la in las.iter() {
+                    let new = Item {
+                        prod: k,
+                        dot: 0,
+                        la: Some(la.clone()),
+                    };
+                    if !items.contains(&new) {
+                        items.push(new);
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    items
+}
+
+///
+/// Build the canonical collection from the starting items.
+///
+/// ## Principles
+/// Start from the closure of the starting items.
+/// For every item set and every symbol `X` after a dot,
+/// GOTO(I, X) is the closure of the items with the dot
+/// moved over `X`. The same kernel is the same state.
+///
+fn gen_automaton<F>(productions: &[Production], start: Vec<Item>, closure: F) -> Automaton
+where
+    F: Fn(&[Item]) -> Vec<Item>,
+{
+    let mut kernels: Vec<Vec<Item>> = vec![start.clone()];
+    let mut states: Vec<Vec<Item>> = vec![closure(&start)];
+    let mut goto: HashMap<(usize, String), usize> = HashMap::new();
+    let mut symbols: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < states.len() {
+        // the symbols after the dot in order
+        let mut nexts: Vec<String> = Vec::new();
+        for item in states[i].iter() {
+            if let Some(x) = item.next(productions) {
+                if !nexts.contains(x) {
+                    nexts.push(x.clone());
+                }
+            }
+        }
+        for x in nexts {
+            let mut kernel: Vec<Item> = states[i]
+                .iter()
+                .filter(|item| item.next(productions) == Some(&x))
+                .map(|item| item.advance())
+                .collect();
+            kernel.sort();
+            let j = match kernels.iter().position(|k| *k == kernel) {
+                Some(j) => j,
+                None => {
+                    states.push(closure(&kernel));
+                    kernels.push(kernel);
+                    states.len() - 1
+                }
+            };
+            if !symbols.contains(&x) {
+                symbols.push(x.clone());
+            }
+            goto.insert((i, x), j);
+        }
+        i += 1;
+    }
+    Automaton {
+        states,
+        goto,
+        symbols,
+    }
+}
+
+///
+/// Build the LR(0) canonical collection.
+///
+/// ## Input
+/// `productions` the augmented productions from `augment()`.
+///
+/// `nts` the non-terminal set of the augmented productions.
+///
+/// ## Principles
+/// Start from `S' -> . S` and call `gen_automaton()`
+/// with `closure()`.
+///
+pub fn lr0_automaton(productions: &[Production], nts: &HashSet<String>) -> Automaton {
+    let start = vec![Item {
+        prod: 0,
+        dot: 0,
+        la: None,
+    }];
+    gen_automaton(productions, start, |kernel| closure(kernel, productions, nts))
+}
+
+///
+/// Build the LR(1) canonical collection.
+///
+/// ## Input
+/// `productions` the augmented productions from `augment()`.
+///
+/// `nts` the non-terminal set of the augmented productions.
+///
+/// `nullable` the set generated from `ll1::gen_nullable()`.
+///
+/// `first` the set generated from `ll1::gen_first()`.
+///
+/// ## Principles
+/// Start from `[S' -> . S, $]` and call `gen_automaton()`
+/// with `closure1()`.
+///
+pub fn lr1_automaton(
+    productions: &[Production],
+    nts: &HashSet<String>,
+    nullable: &HashSet<String>,
+    first: &HashMap<String, HashSet<String>>,
+) -> Automaton {
+    let start = vec![Item {
+        prod: 0,
+        dot: 0,
+        la: Some("$".to_string()),
+    }];
+    gen_automaton(productions, start, |kernel| {
+        closure1(kernel, productions, nts, nullable, first)
+    })
+}
+
+///
+/// Merge the LR(1) states with the same core into LALR(1).
+///
+/// ## Principles
+/// Two states share a core if they have the same items
+/// without the lookaheads. The merged state keeps
+/// the union of the items, and the GOTO transitions
+/// are moved to the merged states. The states are
+/// numbered in the order of the first appearance of the core.
+///
+pub fn lalr_merge(automaton: &Automaton) -> Automaton {
+    let mut cores: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut map: Vec<usize> = Vec::new();
+    let mut states: Vec<Vec<Item>> = Vec::new();
+    for items in automaton.states.iter() {
+        let mut core: Vec<(usize, usize)> = items.iter().map(|item| item.core()).collect();
+        core.sort();
+        core.dedup();
+        let j = match cores.iter().position(|c| *c == core) {
+            Some(j) => j,
+            None => {
+                cores.push(core);
+                states.push(Vec::new());
+                states.len() - 1
+            }
+        };
+        for item in items {
+            if !states[j].contains(item) {
+                states[j].push(item.clone());
+            }
+        }
+        map.push(j);
+    }
+    let goto = automaton
+        .goto
+        .iter()
+        .map(|((i, x), j)| ((map[*i], x.clone()), map[*j]))
+        .collect();
+    Automaton {
+        states,
+        goto,
+        symbols: automaton.symbols.clone(),
+    }
+}
+
+///
+/// An entry in the ACTION table.
+///
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Shift(usize),
+    Reduce(usize),
+    Accept,
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Action::Shift(s) => write!(f, "s{}", s),
+            Action::Reduce(p) => write!(f, "r{}", p),
+            Action::Accept => write!(f, "acc"),
+        }
+    }
+}
+
+///
+/// A struct of LR ACTION/GOTO table.
+///
+/// Every ACTION cell keeps all the actions,
+/// more than one action means a conflict.
+///
+pub struct LrTable {
+    /// the number of states.
+    pub states: usize,
+    action: HashMap<(usize, String), Vec<Action>>,
+    goto: HashMap<(usize, String), usize>,
+}
+
+impl LrTable {
+    ///
+    /// Create an empty table.
+    ///
+    pub fn new(states: usize) -> LrTable {
+        LrTable {
+            states,
+            action: HashMap::new(),
+            goto: HashMap::new(),
+        }
+    }
+
+    ///
+    /// Insert an action, keeping the conflicting ones
+    /// with the shift first.
+    ///
+    pub fn insert(&mut self, state: usize, t: &str, action: Action) {
+        let cell = self.action.entry((state, t.to_string())).or_default();
+        if !cell.contains(&action) {
+            cell.push(action);
+            cell.sort();
+        }
+    }
+
+    ///
+    /// Set GOTO(`state`, `nt`).
+    ///
+    pub fn set_goto(&mut self, state: usize, nt: &str, target: usize) {
+        self.goto.insert((state, nt.to_string()), target);
+    }
+
+    ///
+    /// The actions of `state` on `t`.
+    ///
+    pub fn action(&self, state: usize, t: &str) -> &[Action] {
+        self.action
+            .get(&(state, t.to_string()))
+            .map_or(&[], |v| v.as_slice())
+    }
+
+    ///
+    /// GOTO(`state`, `nt`).
+    ///
+    pub fn goto(&self, state: usize, nt: &str) -> Option<usize> {
+        self.goto.get(&(state, nt.to_string())).copied()
+    }
+
+    ///
+    /// Report the conflicts per state.
+    ///
+    /// ## Principles
+    /// A cell with a shift and a reduce is a shift/reduce
+    /// conflict, and a cell with several reduces is
+    /// a reduce/reduce conflict.
+    ///
+    pub fn conflicts(&self, productions: &[Production]) -> Vec<String> {
+        let mut keys: Vec<&(usize, String)> =
+            self.action.keys().filter(|k| self.action[*k].len() > 1).collect();
+        keys.sort();
+        keys.iter()
+            .map(|k| {
+                let cell = &self.action[*k];
+                let kind = if cell.iter().any(|a| matches!(a, Action::Shift(_))) {
+                    "shift/reduce"
+                } else {
+                    "reduce/reduce"
+                };
+                let detail: Vec<String> = cell
+                    .iter()
+                    .map(|a| match a {
+                        Action::Reduce(p) => format!("{} ({})", a, productions[*p]),
+                        _ => a.to_string(),
+                    })
+                    .collect();
+                format!("State {}: {} conflict on '{}': {}", k.0, kind, k.1, detail.join(" / "))
+            })
+            .collect()
+    }
+
+    ///
+    /// Render the table in the format of `OpTable`.
+    ///
+    /// ## Input
+    /// `tv` the terminals with `$`, as ACTION columns.
+    ///
+    /// `ntv` the non-terminals, as GOTO columns.
+    ///
+    pub fn render(&self, tv: &[String], ntv: &[String]) -> String {
+        let rows: Vec<String> = (0..self.states).map(|s| s.to_string()).collect();
+        let cols: Vec<String> = tv.iter().chain(ntv.iter()).cloned().collect();
+        table::render(&rows, &cols, |i, j| {
+            let state: usize = i.parse().unwrap();
+            if ntv.iter().any(|nt| nt == j) {
+                return match self.goto(state, j) {
+                    Some(s) => s.to_string(),
+                    None => " ".to_string(),
+                };
+            }
+            let cell: Vec<String> = self.action(state, j).iter().map(|a| a.to_string()).collect();
+            if cell.is_empty() {
+                " ".to_string()
+            } else {
+                cell.join("/")
+            }
+        })
+    }
+}
+
+///
+/// Build the ACTION/GOTO table from the automaton.
+///
+/// ## Input
+/// `productions` the augmented productions from `augment()`.
+///
+/// `nts` the non-terminal set of the augmented productions.
+///
+/// `automaton` the collection from `lr0_automaton()`,
+/// `lr1_automaton()` or `lalr_merge()`.
+///
+/// `follow` the FOLLOW sets for SLR(1),
+/// or `None` for LR(0) which reduces on every terminal.
+/// It is not used by the items with lookaheads.
+///
+/// `tv` the terminals with `$`.
+///
+/// ## Principles
+/// - `A -> x . a y` with terminal `a`: shift GOTO(I, a).
+/// - `S' -> S .`: accept on `$`.
+/// - `[A -> x ., a]`: reduce on the lookahead `a`.
+/// - `A -> x .`: reduce on FOLLOW(A), or on all terminals.
+/// - GOTO(I, A) for the non-terminals.
+///
+pub fn gen_lr_table(
+    productions: &[Production],
+    nts: &HashSet<String>,
+    automaton: &Automaton,
+    follow: Option<&HashMap<String, HashSet<String>>>,
+    tv: &[String],
+) -> LrTable {
+    let mut table = LrTable::new(automaton.states.len());
+    for (i, items) in automaton.states.iter().enumerate() {
+        for item in items {
+            match item.next(productions) {
+                Some(x) if !nts.contains(x) => {
+                    table.insert(i, x, Action::Shift(automaton.goto[&(i, x.clone())]));
+                }
+                Some(_) => {}
+                None if item.prod == 0 => table.insert(i, "$", Action::Accept),
+                None => match &item.la {
+                    Some(la) => table.insert(i, la, Action::Reduce(item.prod)),
+                    None => {
+                        let left = &productions[item.prod].left;
+                        for t in tv {
+                            if follow.is_none_or(|f| f[left].contains(t)) {
+                                table.insert(i, t, Action::Reduce(item.prod));
+                            }
+                        }
+                    }
+                },
+            }
+        }
+    }
+    for ((i, x), j) in automaton.goto.iter() {
+        if nts.contains(x) {
+            table.set_goto(*i, x, *j);
+        }
+    }
+    table
+}
+
+///
+/// Resolve the shift/reduce conflicts
+/// with `%left` and `%right` precedence.
+///
+/// ## Input
+/// `table` the mutable `LrTable`.
+///
+/// `productions` the augmented productions from `augment()`.
+///
+/// `prec` the precedence from `gen_precedence()`.
+///
+/// ## Return
+/// The description of the resolved conflicts.
+///
+/// ## Principles
+/// The precedence of a production is the one of the
+/// rightmost terminal with a precedence. For a conflict
+/// between shifting `a` and reducing `p`:
+/// - the higher precedence wins;
+/// - on the same level, `%left` reduces, `%right` shifts,
+///   and `%nonassoc` leaves an error entry.
+///
+/// Conflicts without precedence on both sides are kept.
+///
+pub fn resolve(
+    table: &mut LrTable,
+    productions: &[Production],
+    prec: &HashMap<String, (usize, Assoc)>,
+) -> Vec<String> {
+    let mut resolved: Vec<String> = Vec::new();
+    let mut keys: Vec<(usize, String)> = table.action.keys().cloned().collect();
+    keys.sort();
+    for key in keys {
+        let cell = &table.action[&key];
+        let (s, p) = match cell.as_slice() {
+            [Action::Shift(s), Action::Reduce(p)] => (*s, *p),
+            _ => continue,
+        };
+        let pprec = productions[p].right.iter().rev().find_map(|v| prec.get(v));
+        let (tlevel, assoc) = match (prec.get(&key.1), pprec) {
+            (Some(t), Some(r)) => {
+                if t.0 != r.0 {
+                    (t.0, if t.0 > r.0 { Assoc::Right } else { Assoc::Left })
+                } else {
+                    *t
+                }
+            }
+            _ => continue,
+        };
+        let (kept, how) = match assoc {
+            Assoc::Left => (vec![Action::Reduce(p)], "reduce"),
+            Assoc::Right => (vec![Action::Shift(s)], "shift"),
+            Assoc::Nonassoc => (Vec::new(), "error"),
+        };
+        resolved.push(format!(
+            "State {}: conflict on '{}' resolved as {} (level {}, {})",
+            key.0, key.1, how, tlevel, productions[p]
+        ));
+        if kept.is_empty() {
+            table.action.remove(&key);
+        } else {
+            table.action.insert(key, kept);
+        }
+    }
+    resolved
+}
+
+///
+/// Format the item sets and the GOTO transitions.
+///
+pub fn format_automaton(productions: &[Production], automaton: &Automaton) -> String {
+    let mut output = String::new();
+    for (i, items) in automaton.states.iter().enumerate() {
+        output += &format!("I{}:\n", i);
+        // group the lookaheads of the same core
+        let mut cores: Vec<(&Item, Vec<&str>)> = Vec::new();
+        for item in items {
+            match cores.iter_mut().find(|(c, _)| c.core() == item.core()) {
+                Some((_, las)) => las.extend(item.la.as_deref()),
+                None => cores.push((item, item.la.as_deref().into_iter().collect())),
+            }
+        }
+        for (item, las) in cores {
+            if las.is_empty() {
+                output += &format!("\t{}\n", item.format(productions));
+            } else {
+                output += &format!("\t{}, {}\n", item.format(productions), las.join("/"));
+            }
+        }
+    }
+    output += "\n";
+    for (i, _) in automaton.states.iter().enumerate() {
+        for x in automaton.symbols.iter() {
+            if let Some(j) = automaton.goto.get(&(i, x.clone())) {
+                output += &format!("GOTO(I{}, {}) = I{}\n", i, x, j);
+            }
+        }
+    }
+    output
+}
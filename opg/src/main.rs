@@ -1,427 +1,1229 @@
-//! # Operator Precedence Grammar Parser
+//! # opg
 //!
-//! `opg` reads an context-free grammar input 
-//! and outputs the precedence of the operators.
-
-mod dfs;
-mod table;
+//! The command line interface of `opg`,
+//! which prints the tables and the results
+//! of the analyses on a grammar file.
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 use std::env;
 use std::fs;
+use std::path::Path;
+use std::process;
+
+use opg::compress::{self, Layout};
+use opg::{
+    antlr, binary, bison, check, cnf, codegen, cyk, derivation, earley, hygiene, json, lexer, ll1, lr, parser, pratt, quad,
+    semantic, sentence, simple, table, transform,
+};
+use opg::{
+    format_grammar, gen_precedence, gen_productions, gen_table, gen_weights, get_non_terminals, get_terminals,
+    ordered_symbols, Production, EPSILON,
+};
 
 ///
-/// A struct to 
-/// represent a production.
+/// Check the hygiene of the grammar with
+/// the terminals in the precedence directives.
 ///
-struct Production {
-    /// the left side of the production.
-    left: String,
-    /// the right side of the production.
-    right: Vec<String>,
+fn check_hygiene(contents: &str, productions: &[Production]) -> hygiene::Report {
+    let mut declared: Vec<String> = gen_precedence(contents).into_keys().collect();
+    declared.sort();
+    hygiene::check(productions, &declared)
 }
 
 ///
-/// Generate FIRSTVT set for
-/// every non-terminals.
+/// Print the hygiene warnings and the derivation
+/// cycles to stderr before the table construction.
 ///
-/// ## Input
-/// `productions` the vector of struct `Production`
+fn warn_hygiene(contents: &str, productions: &[Production]) {
+    for w in check_hygiene(contents, productions).warnings(productions) {
+        eprintln!("warning: {}", w);
+    }
+    for cycle in transform::find_cycles(productions) {
+        eprintln!("warning: derivation cycle {}", transform::format_cycle(productions, &cycle));
+    }
+}
+
 ///
-/// `nts` the non-terminal set, which could be generated 
-/// from the function `get_non_terminals()`. 
+/// Print the hygiene report of the grammar contents.
 ///
-/// ## Return
-/// `firstvt` the set of FIRSTVT
-/// for all terminals.
+/// ## Input
+/// `contents` The string read from file.
 ///
 /// ## Example
 /// ```
-///  let firstvt:HashMap<String, HashSet<String>> = gen_firstvt(&productions, &nts);
+/// opg_hygiene(&contents);
 /// ```
 ///
 /// ## Principles
-/// Go through every production
-/// and search the following patterns:
-/// 1. U => U_1y
-/// 2. U => U_1Ty
-/// 3. U => Ty
-///
-/// call `dfs::compose_element()` 
-/// to get the final result.
-///
-fn gen_firstvt(
-    productions: &Vec<Production>,
-    nts: &HashSet<String>,
-) -> HashMap<String, HashSet<String>> {
-    let mut firstvtmono: HashMap<String, HashSet<String>> = HashMap::new();
-    let mut firstvtcon: HashMap<String, HashSet<String>> = HashMap::new();
-
-    // Find mono terminal and
-    // record the containing part
-    for p in productions {
-        if nts.contains(p.right.first().unwrap()) {
-            // Case 1: U => U_1y
-            let ntc = firstvtcon
-                .entry(p.left.to_string())
-                .or_insert(HashSet::new());
-            ntc.insert(p.right.first().unwrap().to_string());
-            // Case 1*: U => U_1Ty
-            if p.right.len() > 1 && !nts.contains(&p.right[1]) {
-                let vts = firstvtmono
-                    .entry(p.left.to_string())
-                    .or_insert(HashSet::new());
-                vts.insert(p.right[1].clone());
-            }
-        } else {
-            // Case 2: U => Ty
-            let vts = firstvtmono
-                .entry(p.left.to_string())
-                .or_insert(HashSet::new());
-            vts.insert(p.right.first().unwrap().to_string());
-        }
-    }
-    let firstvt = dfs::compose_elements(&firstvtmono, &firstvtcon);
-    firstvt
+/// See `hygiene::check()` and `transform::find_cycles()`.
+/// The useless parts could be removed by
+/// `opg transform <grammar> --useless`, and the cycles of
+/// the unit productions by `opg transform <grammar> --unit`.
+///
+fn opg_hygiene(contents: &str) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let report = check_hygiene(contents, &productions);
+    let cycles = transform::find_cycles(&productions);
+    if report.is_clean() && cycles.is_empty() {
+        println!("The grammar is clean.");
+        return;
+    }
+    for w in report.warnings(&productions) {
+        println!("{}", w);
+    }
+    for cycle in cycles {
+        println!("derivation cycle {}", transform::format_cycle(&productions, &cycle));
+    }
 }
 
 ///
-/// Generate LASTVT set for
-/// every non-terminals.
+/// Generate Operator Precedence Table
+/// for context-free grammar contents.
 ///
 /// ## Input
-/// `productions` the vector of struct `Production`
+/// `contents` The string read from file.
 ///
-/// `nts` the non-terminal set, which could be generated 
-/// from the function `get_non_terminals()`. 
+/// ## Example
+/// ```
+/// // Contents of the file
+/// let contents = fs::read_to_string(filename).expect("No such file.");
+/// // Get the table
+/// opg_generate(&contents);
+/// ```
 ///
-/// ## Return
-/// `lastvt` the set of LASTVT
-/// for all terminals.
+/// ## Principles
+/// Parse the productions, warn about the useless parts
+/// by `warn_hygiene()` and call `gen_table()`.
+/// If there is conflict on operator precedence,
+/// then the grammar is ambiguous and the panic will be fired.
+/// Finally, print the `OpTable`.
+///
+fn opg_generate(contents: &str) {
+    let productions: Vec<Production> = gen_productions(contents);
+    warn_hygiene(contents, &productions);
+    let table = gen_table(&productions);
+    if !table.conflicts().is_empty() {
+        println!("The grammar is ambiguous.");
+        panic!("Ambiguous grammar detected.");
+    }
+
+    print!("{}", table);
+    fs::write("output.txt", table.to_string()).expect("Cannot output file!");
+}
+
+///
+/// Evaluate expressions with the
+/// semantic actions of the grammar.
+///
+/// ## Input
+/// `contents` The string read from file.
+///
+/// `exprs` the expressions to be evaluated.
 ///
 /// ## Example
 /// ```
-///  let lastvt:HashMap<String, HashSet<String>> = gen_lastvt(&productions, &nts);
+/// opg_eval(&contents, &["1 + 2 * 3".to_string()]);
 /// ```
 ///
 /// ## Principles
-/// Go through every production
-/// and search the following patterns:
-/// 1. U => xU_1
-/// 2. U => xTU_1
-/// 3. U => xT
-///
-/// call `dfs::compose_element()` 
-/// to get the final result.
-///
-fn gen_lastvt(
-    productions: &Vec<Production>,
-    nts: &HashSet<String>,
-) -> HashMap<String, HashSet<String>> {
-    let mut lastvtmono: HashMap<String, HashSet<String>> = HashMap::new();
-    let mut lastvtcon: HashMap<String, HashSet<String>> = HashMap::new();
-
-    // Find mono terminal and
-    // record the containing part
-    for p in productions {
-        if nts.contains(p.right.last().unwrap()) {
-            // Case 1: U => xU_1
-            let ntc = lastvtcon
-                .entry(p.left.to_string())
-                .or_insert(HashSet::new());
-            ntc.insert(p.right.last().unwrap().to_string());
-            // Case 1*: U => xTU_1
-            if p.right.len() > 1 && !nts.contains(&p.right[p.right.len() - 2]) {
-                let vts = lastvtmono
-                    .entry(p.left.to_string())
-                    .or_insert(HashSet::new());
-                vts.insert(p.right[p.right.len() - 2].clone());
-            }
-        } else {
-            // Case 2: U => xT
-            let vts = lastvtmono
-                .entry(p.left.to_string())
-                .or_insert(HashSet::new());
-            vts.insert(p.right.last().unwrap().to_string());
-        }
-    }
-    let lastvt = dfs::compose_elements(&lastvtmono, &lastvtcon);
-    lastvt
+/// Build the `OpTable` by `gen_table()`, split every
+/// expression into tokens by `lexer::tokenize()`, and
+/// run `parser::parse()` with a `semantic::Evaluator`,
+/// which executes the action at every reduction.
+///
+fn opg_eval(contents: &str, exprs: &[String]) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let table = gen_table(&productions);
+    if !table.conflicts().is_empty() {
+        eprintln!("The grammar is ambiguous.");
+        process::exit(1);
+    }
+    let nts = get_non_terminals(&productions);
+    for expr in exprs {
+        let result = lexer::tokenize(expr, table.terminals()).and_then(|tokens| {
+            parser::parse(&table, &productions, &nts, &tokens, &mut semantic::Evaluator)
+        });
+        match result {
+            Ok(value) => println!("{} = {}", expr, value),
+            Err(e) => {
+                eprintln!("{}: {}", expr, e);
+                process::exit(1);
+            }
+        }
+    }
 }
 
 ///
-/// Find the equal operators
+/// Translate expressions into quadruples
+/// with the templates of the grammar.
 ///
 /// ## Input
-/// `table` the mutable `OpTable` struct for output
+/// `contents` The string read from file.
+///
+/// `exprs` the expressions to be translated.
+///
+/// ## Example
+/// ```
+/// opg_translate(&contents, &["a + b * c".to_string()]);
+/// ```
+///
+/// ## Principles
+/// Run `parser::parse()` with a `quad::Translator`,
+/// which emits quadruples with fresh temporaries
+/// at every reduction. Then print them as a quadruple
+/// table and as three-address code.
+///
+fn opg_translate(contents: &str, exprs: &[String]) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let table = gen_table(&productions);
+    if !table.conflicts().is_empty() {
+        eprintln!("The grammar is ambiguous.");
+        process::exit(1);
+    }
+    let nts = get_non_terminals(&productions);
+    for expr in exprs {
+        let mut translator = quad::Translator::new(lexer::operand_terminal(table.terminals()));
+        let result = lexer::tokenize(expr, table.terminals()).and_then(|tokens| {
+            parser::parse(&table, &productions, &nts, &tokens, &mut translator)
+        });
+        if let Err(e) = result {
+            eprintln!("{}: {}", expr, e);
+            process::exit(1);
+        }
+        println!("{}", expr);
+        print!("{}", quad::quad_table(&translator.quads));
+        println!();
+        print!("{}", quad::tac(&translator.quads));
+    }
+}
+
 ///
-/// `productions` the vector of struct `Production`
+/// Generate the LL(1) analysis
+/// for context-free grammar contents.
 ///
-/// `nts` the non-terminal set, which could be generated 
-/// from the function `get_non_terminals()`. 
+/// ## Input
+/// `contents` The string read from file.
 ///
 /// ## Example
 /// ```
-/// find_eq(&mut table, &productions, &nts);
+/// opg_ll1(&contents);
 /// ```
 ///
 /// ## Principles
-/// Search the pattern of ..T1..T2..
-/// and make T1=T2, notice that it is
-/// NOT indicate that T2=T1.
-/// 
-fn find_eq(
-    table: &mut table::OpTable,
-    productions: &Vec<Production>,
-    nts: &HashSet<String>,
-) {
-    for p in productions {
-        // Get all terminals in the right side
-        let mut pe = p.right.clone();
-        pe.retain(|x| !nts.contains(x));
-        // Since the relation is not commutative
-        // Equal will be assigned from left to right.
-        for i in 0..pe.len() {
-            for j in i + 1..pe.len() {
-                table.insert(&(pe[i].clone(), pe[j].clone()), '=');
-            }
+/// Compute nullable, FIRST and FOLLOW sets
+/// by the `ll1` module, then print the sets,
+/// the predictive parsing table and the conflicts.
+///
+fn opg_ll1(contents: &str) {
+    let productions: Vec<Production> = gen_productions(contents);
+    warn_hygiene(contents, &productions);
+    let nts = get_non_terminals(&productions);
+    let ts = get_terminals(&productions, &nts);
+    let (ntv, mut tv) = ordered_symbols(&productions, &nts);
+    tv.retain(|t| ts.contains(t));
+    tv.push("$".to_string());
+
+    let nullable = ll1::gen_nullable(&productions);
+    let first = ll1::gen_first(&productions, &nts, &nullable);
+    let follow = ll1::gen_follow(&productions, &nts, &nullable, &first);
+    let table = ll1::Ll1Table::new(&productions, &nts, &nullable, &first, &follow);
+
+    println!("NULLABLE = {}", ll1::format_set(&ntv, &nullable));
+    for nt in ntv.iter() {
+        let mut fs = first[nt].clone();
+        if nullable.contains(nt) {
+            fs.insert(EPSILON.to_string());
+        }
+        println!("FIRST({}) = {}", nt, ll1::format_set(&tv, &fs));
+    }
+    for nt in ntv.iter() {
+        println!("FOLLOW({}) = {}", nt, ll1::format_set(&tv, &follow[nt]));
+    }
+    println!();
+    print!("{}", table.render(&productions, &ntv, &tv));
+    let conflicts = table.conflicts(&productions);
+    if conflicts.is_empty() {
+        println!("The grammar is LL(1).");
+    } else {
+        println!();
+        for c in conflicts {
+            println!("{}", c);
         }
+        println!("The grammar is not LL(1).");
     }
 }
 
 ///
-/// Find the less relations
+/// Generate the LR analysis
+/// for context-free grammar contents.
 ///
 /// ## Input
-/// `table` the mutable `OpTable` struct for output
+/// `contents` The string read from file.
 ///
-/// `productions` the vector of struct `Production`
+/// `kind` one of `lr0`, `slr`, `lr1` and `lalr`.
 ///
-/// `nts` the non-terminal set, which could be generated 
-/// from the function `get_non_terminals()`.
+/// `use_prec` resolve the shift/reduce conflicts
+/// by `%left` and `%right` if true.
+///
+/// ## Example
+/// ```
+/// opg_lr(&contents, "lalr", true);
+/// ```
+///
+/// ## Principles
+/// Augment the grammar with `S' -> S`, and build the collection:
+/// - `lr0` and `slr`: `lr::lr0_automaton()`, where `slr`
+///   reduces on the FOLLOW sets.
+/// - `lr1`: `lr::lr1_automaton()`.
+/// - `lalr`: `lr::lalr_merge()` on the LR(1) collection.
+///
+/// Then build the table by `lr::gen_lr_table()` and print the
+/// numbered productions, the item sets, the ACTION/GOTO table,
+/// the conflicts and the number of states.
+///
+fn opg_lr(contents: &str, kind: &str, use_prec: bool) {
+    let productions = gen_productions(contents);
+    warn_hygiene(contents, &productions);
+    let productions = lr::augment(&productions);
+    let nts = get_non_terminals(&productions);
+    let (ntv, mut tv) = ordered_symbols(&productions, &nts);
+    tv.push("$".to_string());
+
+    let nullable = ll1::gen_nullable(&productions);
+    let first = ll1::gen_first(&productions, &nts, &nullable);
+    let follow = ll1::gen_follow(&productions, &nts, &nullable, &first);
+    let (automaton, name) = match kind {
+        "lr0" => (lr::lr0_automaton(&productions, &nts), "LR(0)"),
+        "slr" => (lr::lr0_automaton(&productions, &nts), "SLR(1)"),
+        "lr1" => (lr::lr1_automaton(&productions, &nts, &nullable, &first), "LR(1)"),
+        _ => {
+            let lr1 = lr::lr1_automaton(&productions, &nts, &nullable, &first);
+            println!("LR(1) states: {}", lr1.states.len());
+            (lr::lalr_merge(&lr1), "LALR(1)")
+        }
+    };
+    let follow = if kind == "slr" { Some(&follow) } else { None };
+    let mut table = lr::gen_lr_table(&productions, &nts, &automaton, follow, &tv);
+
+    for (i, p) in productions.iter().enumerate() {
+        println!("({}) {}", i, p);
+    }
+    println!();
+    print!("{}", lr::format_automaton(&productions, &automaton));
+    println!();
+    if use_prec {
+        for r in lr::resolve(&mut table, &productions, &gen_precedence(contents)) {
+            println!("{}", r);
+        }
+        println!();
+    }
+    print!("{}", table.render(&tv, &ntv[1..]));
+    let conflicts = table.conflicts(&productions);
+    println!();
+    for c in conflicts.iter() {
+        println!("{}", c);
+    }
+    println!(
+        "{} states, {} conflicts. The grammar is {}{}.",
+        table.states,
+        conflicts.len(),
+        if conflicts.is_empty() { "" } else { "not " },
+        name
+    );
+}
+
+///
+/// Generate the simple precedence analysis
+/// for context-free grammar contents.
+///
+/// ## Input
+/// `contents` The string read from file.
 ///
-/// `firstvt` the FIRSTVT set generated from the function
-/// `gen_firstvt()`. 
+/// `sentence` the sentence to be parsed, if any.
 ///
 /// ## Example
 /// ```
-/// let firstvt = gen_firstvt(&productions, &nts);
-/// find_less(&mut table, &productions, &nts, &firstvt);
+/// opg_simple(&contents, Some("i + i * i"));
 /// ```
 ///
 /// ## Principles
-/// Find ...T1U1..., where T2 is in FIRSTVT(U1) and
-/// make T1<T2. Notice that this doesn't 
-/// indicate that T2>T1.
-/// 
-fn find_less(
-    table: &mut table::OpTable,
-    productions: &Vec<Production>,
-    nts: &HashSet<String>,
-    firstvt: &HashMap<String, HashSet<String>>,
-) {
-    for p in productions {
-        if p.right.len() <= 1 {
-            continue;
+/// Generate HEAD+ and TAIL+ by `simple::gen_head()` and
+/// `simple::gen_tail()`, build the relations between all
+/// the symbols by `simple::SpTable::new()`, then print the
+/// sets, the matrix and the conflicts. If there is no conflict,
+/// parse the sentence and print the steps.
+///
+fn opg_simple(contents: &str, sentence: Option<&str>) {
+    let productions: Vec<Production> = gen_productions(contents);
+    warn_hygiene(contents, &productions);
+    let nts = get_non_terminals(&productions);
+    let (ntv, tv) = ordered_symbols(&productions, &nts);
+    let symbols: Vec<String> = ntv
+        .iter()
+        .chain(tv.iter())
+        .cloned()
+        .chain(std::iter::once("$".to_string()))
+        .collect();
+
+    let head = simple::gen_head(&productions, &nts);
+    let tail = simple::gen_tail(&productions, &nts);
+    for nt in ntv.iter() {
+        println!("HEAD+({}) = {}", nt, ll1::format_set(&symbols, &head[nt]));
+    }
+    for nt in ntv.iter() {
+        println!("TAIL+({}) = {}", nt, ll1::format_set(&symbols, &tail[nt]));
+    }
+    println!();
+    let table = simple::SpTable::new(&productions, &nts, &head, &tail);
+    print!("{}", table.render(&symbols));
+    let conflicts = table.conflicts();
+    if !conflicts.is_empty() {
+        println!();
+        for c in conflicts.iter() {
+            println!("{}", c);
         }
-        for i in 0..p.right.len() - 1 {
-            if !nts.contains(&p.right[i]) && nts.contains(&p.right[i + 1]) {
-                for t in firstvt[&p.right[i + 1]].iter() {
-                    table.insert(&(p.right[i].to_owned(), t.to_owned()), '<');
+        println!("The grammar is not a simple precedence grammar.");
+        return;
+    }
+    println!("The grammar is a simple precedence grammar.");
+    if let Some(sentence) = sentence {
+        let ts = get_terminals(&productions, &nts);
+        let result = lexer::tokenize(sentence, &ts).and_then(|tokens| simple::parse(&table, &productions, &tokens));
+        println!();
+        match result {
+            Ok(steps) => {
+                println!("Stack\tInput\tAction");
+                for (stack, input, action) in steps {
+                    println!("{}\t{}\t{}", stack, input, action);
                 }
             }
+            Err(e) => {
+                eprintln!("{}: {}", sentence, e);
+                process::exit(1);
+            }
         }
     }
 }
 
 ///
-/// Find the greater relations
+/// Parse a sentence with the Earley parser
+/// for any context-free grammar contents.
 ///
 /// ## Input
-/// `table` the mutable `OpTable` struct for output
+/// `contents` The string read from file.
+///
+/// `sentence` the sentence to be parsed.
+///
+/// ## Example
+/// ```
+/// opg_earley(&contents, "id + id * id");
+/// ```
 ///
-/// `productions` the vector of struct `Production`
+/// ## Principles
+/// Recognize the tokens by `earley::Earley::recognize()`,
+/// and print the item sets. If it is accepted, print the
+/// shared packed parse forest and the number of derivations.
 ///
-/// `nts` the non-terminal set, which could be generated 
-/// from the function `get_non_terminals()`.
+fn opg_earley(contents: &str, sentence: &str) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let nts = get_non_terminals(&productions);
+    let ts = get_terminals(&productions, &nts);
+    let tokens = match lexer::tokenize(sentence, &ts) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            eprintln!("{}: {}", sentence, e);
+            process::exit(1);
+        }
+    };
+    let kinds: Vec<String> = tokens[..tokens.len() - 1].iter().map(|t| t.kind.clone()).collect();
+
+    let mut parser = earley::Earley::new(&productions, &nts);
+    let accepted = parser.recognize(&kinds);
+    print!("{}", earley::format_chart(&productions, &parser.chart));
+    println!();
+    match parser.forest() {
+        Some(forest) if accepted => {
+            print!("{}", forest.format(&productions));
+            println!();
+            match forest.count() {
+                Some(n) => println!("Accepted with {} derivation(s).", n),
+                None => println!("Accepted with infinitely many derivations."),
+            }
+        }
+        _ => println!("Rejected."),
+    }
+}
+
 ///
-/// `lastvt` the LASTVT set generated from the function
-/// `gen_lastvt()`. 
+/// Print the parse tree, the leftmost and the rightmost
+/// derivations of a sentence of the grammar contents.
+///
+/// ## Input
+/// `contents` The string read from file.
+///
+/// `sentence` the sentence to be derived.
+///
+/// `latex` whether to print the derivations
+/// in the LaTeX `align*` environment.
 ///
 /// ## Example
 /// ```
-/// let lastvt = gen_firstvt(&productions, &nts);
-/// find_greater(&mut table, &productions, &nts, &lastvt);
+/// opg_derive(&contents, "i + i * i", false);
 /// ```
 ///
 /// ## Principles
-/// Find ...U1T2..., where T1 is in LASTVT(U1) and
-/// make T1>T2. Notice that this doesn't 
-/// indicate that T2<T1.
-/// 
-fn find_greater(
-    table: &mut table::OpTable,
-    productions: &Vec<Production>,
-    nts: &HashSet<String>,
-    lastvt: &HashMap<String, HashSet<String>>,
-) {
-    for p in productions {
-        if p.right.len() <= 1 {
-            continue;
+/// Parse the tokens by `earley::Earley`, take a parse tree out
+/// of the forest by `derivation::extract_tree()`, and expand it
+/// by `derivation::derive()`. For an ambiguous sentence, only
+/// the tree of the first packed alternatives is printed.
+///
+fn opg_derive(contents: &str, sentence: &str, latex: bool) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let nts = get_non_terminals(&productions);
+    let ts = get_terminals(&productions, &nts);
+    let tokens = match lexer::tokenize(sentence, &ts) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            eprintln!("{}: {}", sentence, e);
+            process::exit(1);
         }
-        for i in 0..p.right.len() - 1 {
-            if nts.contains(&p.right[i]) && !nts.contains(&p.right[i + 1]) {
-                for t in lastvt[&p.right[i]].iter() {
-                    table.insert( &(t.to_owned(), p.right[i + 1].to_owned()), '>');
-                }
+    };
+    let kinds: Vec<String> = tokens[..tokens.len() - 1].iter().map(|t| t.kind.clone()).collect();
+
+    let mut parser = earley::Earley::new(&productions, &nts);
+    parser.recognize(&kinds);
+    let tree = match parser.forest().as_ref().and_then(derivation::extract_tree) {
+        Some(tree) => tree,
+        None => {
+            eprintln!("{}: not a sentence of the grammar", sentence);
+            process::exit(1);
+        }
+    };
+    let format = if latex {
+        derivation::format_latex
+    } else {
+        derivation::format_derivation
+    };
+    if !latex {
+        println!("Parse tree:");
+        print!("{}", derivation::format_tree(&tree, &productions));
+        println!();
+    }
+    for (name, rightmost) in [("Leftmost", false), ("Rightmost", true)] {
+        let (start, steps) = derivation::derive(&tree, &productions, rightmost);
+        println!("{} derivation:", name);
+        print!("{}", format(&start, &steps, &productions));
+        println!();
+    }
+}
+
+///
+/// Generate the source code of a standalone
+/// parser for the grammar contents.
+///
+/// ## Input
+/// `contents` The string read from file.
+///
+/// `options` the options:
+/// - `--lang rust|c`: the target language, `rust` by default.
+/// - `--output BASE`: write `BASE.rs`, or `BASE.h` and `BASE.c`.
+///   The Rust code is printed if it is not given, and the C code
+///   is written into `parser.h` and `parser.c`.
+/// - `--prefix NAME`: the prefix of the names in C, `opg` by default.
+/// - `--layout dense|functions|merged|packed`: the representation
+///   of the relation, `dense` by default, see `compress::Layout`.
+///
+/// ## Example
+/// ```
+/// opg_codegen(&contents, &["--lang".to_string(), "c".to_string()]);
+/// ```
+///
+/// ## Principles
+/// Build the `OpTable` by `gen_table()` and generate
+/// the code by `codegen::rust()` or `codegen::c()`,
+/// with the terminals in the order of appearance.
+///
+fn opg_codegen(contents: &str, options: &[String]) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let table = gen_table(&productions);
+    if !table.conflicts().is_empty() {
+        eprintln!("The grammar is ambiguous.");
+        process::exit(1);
+    }
+    let nts = get_non_terminals(&productions);
+    let (_, mut tv) = ordered_symbols(&productions, &nts);
+    tv.push("$".to_string());
+    let options = named_options(options, &["--lang", "--output", "--prefix", "--layout"]);
+    let output = options.get("--output");
+    let layout = match options.get("--layout").map_or("dense", |s| s.as_str()) {
+        "dense" => Layout::Dense,
+        "functions" => Layout::Functions,
+        "merged" => Layout::Merged,
+        "packed" => Layout::Packed,
+        layout => {
+            eprintln!("Unknown layout: {}", layout);
+            process::exit(1);
+        }
+    };
+    if layout == Layout::Functions && table.gen_functions(&tv).is_none() {
+        eprintln!("The precedence functions do not exist, the table is compressed otherwise.");
+    }
+    match options.get("--lang").map_or("rust", |s| s.as_str()) {
+        "rust" => {
+            let code = codegen::rust(&table, &productions, &nts, &tv, layout);
+            match output {
+                Some(base) => fs::write(format!("{}.rs", base), code).expect("Cannot output file!"),
+                None => print!("{}", code),
             }
         }
+        "c" => {
+            let base = output.map_or("parser", |s| s.as_str());
+            let prefix = options.get("--prefix").map_or("opg", |s| s.as_str());
+            let header = format!("{}.h", base);
+            // the header is included by the name beside the source
+            let name = std::path::Path::new(&header).file_name().unwrap().to_string_lossy();
+            let (h, c) = codegen::c(&table, &productions, &nts, &tv, prefix, &name, layout);
+            fs::write(&header, h).expect("Cannot output file!");
+            fs::write(format!("{}.c", base), c).expect("Cannot output file!");
+        }
+        lang => {
+            eprintln!("Unknown language: {}", lang);
+            process::exit(1);
+        }
     }
 }
 
 ///
-/// Generate production list for
-/// the grammar contents.
+/// Print the binding powers of the operators inferred
+/// from the table, or the skeleton of a Pratt parser.
 ///
 /// ## Input
-/// `contents` the string read from file.
+/// `contents` The string read from file.
 ///
-/// ## Output
-/// `p` the vector of productions.
+/// `options` the options:
+/// - `--skeleton`: print the Rust skeleton instead of the table.
+/// - `--output FILE`: write the skeleton into FILE.
 ///
 /// ## Example
 /// ```
-/// let mut productions: Vec<Production> = gen_productions(&contents);
+/// opg_pratt(&contents, &["--skeleton".to_string()]);
 /// ```
 ///
-/// ## Principle
-/// For every line in the file, split it on "->".
-/// Then split the trimmed right side based on "|".
-/// After processing, push the new `Production` struct
-/// into the result.
+/// ## Principles
+/// See `pratt::infer()` and `pratt::skeleton()`. The operators
+/// which do not fit are reported to stderr, and the process
+/// exits with 1 if there is any.
 ///
-fn gen_productions(contents: &String) -> Vec<Production> {
-    let mut p: Vec<Production> = Vec::new();
-    for line in contents.lines() {
-        let ps: Vec<_> = line.split("->").collect();
-        let ls = ps[0].trim();
-        let rs: Vec<_> = ps[1].split("|").collect();
-        for rsp in rs {
-            let vs: Vec<_> = rsp.split_whitespace().collect();
-            p.push(Production {
-                left: ls.to_string(),
-                right: vs.iter().map(|s| s.to_string()).collect(),
-            });
-        }
-    }
-    p
+fn opg_pratt(contents: &str, options: &[String]) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let table = gen_table(&productions);
+    if !table.conflicts().is_empty() {
+        eprintln!("The grammar is ambiguous.");
+        process::exit(1);
+    }
+    let nts = get_non_terminals(&productions);
+    let (_, tv) = ordered_symbols(&productions, &nts);
+    let powers = pratt::infer(&table, &productions, &nts, &tv);
+    let skeleton = options.iter().any(|o| o == "--skeleton");
+    let flags: Vec<String> = options.iter().filter(|o| *o != "--skeleton").cloned().collect();
+    let options = named_options(&flags, &["--output"]);
+    if !skeleton {
+        print!("{}", pratt::format_powers(&powers));
+    } else {
+        let code = pratt::skeleton(&powers, &productions);
+        match options.get("--output") {
+            Some(file) => fs::write(file, code).expect("Cannot output file!"),
+            None => print!("{}", code),
+        }
+    }
+    for m in powers.misfits.iter() {
+        eprintln!("does not fit: {}", m);
+    }
+    if !powers.misfits.is_empty() {
+        process::exit(1);
+    }
 }
 
 ///
-/// Get all the non terminals from
-/// the generated production.
+/// Export the grammar contents for the other tools.
 ///
 /// ## Input
-/// `productions` the vector of struct `Production`
+/// `contents` The string read from file.
+///
+/// `options` the options:
+/// - `--format bison|antlr`: the format, `bison` by default.
+/// - `--output FILE`: write FILE instead of printing.
+/// - `--name NAME`: the name of the ANTLR grammar, the
+///   stem of FILE or `Grammar` by default.
 ///
-/// ## Output
-/// The hashset contains non-terminals.
-/// 
 /// ## Example
 /// ```
-/// let nts = get_non_terminals(&productions);
+/// opg_export(&contents, &["--format".to_string(), "antlr".to_string()]);
 /// ```
 ///
 /// ## Principles
-/// Collect all the symbol on the left side
-/// in the productions.
+/// See `bison::export()` and `antlr::export()`. The precedence
+/// for bison is from the directives like `%left` in the contents,
+/// or derived from the table by `bison::derive_levels()` if there
+/// is none.
 ///
-fn get_non_terminals(productions: &Vec<Production>) -> HashSet<String> {
-    productions.iter().map(|s| s.left.clone()).collect()
+fn opg_export(contents: &str, options: &[String]) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let options = named_options(options, &["--format", "--output", "--name"]);
+    let output = options.get("--output");
+    let code = match options.get("--format").map_or("bison", |s| s.as_str()) {
+        "bison" => {
+            let prec = gen_precedence(contents);
+            let table = gen_table(&productions);
+            let levels = if !prec.is_empty() {
+                bison::declared_levels(&prec)
+            } else if table.conflicts().is_empty() {
+                let nts = get_non_terminals(&productions);
+                let (_, tv) = ordered_symbols(&productions, &nts);
+                bison::derive_levels(&table, &productions, &nts, &tv)
+            } else {
+                eprintln!("warning: the grammar is ambiguous, so the precedence is not derived");
+                Vec::new()
+            };
+            bison::export(&productions, &levels)
+        }
+        "antlr" => {
+            let stem = output.and_then(|f| Path::new(f).file_stem()).and_then(|s| s.to_str());
+            let name = options.get("--name").map_or(stem.unwrap_or("Grammar"), |s| s.as_str());
+            antlr::export(&productions, name)
+        }
+        format => {
+            eprintln!("Unknown format: {}", format);
+            process::exit(1);
+        }
+    };
+    match output {
+        Some(file) => fs::write(file, code).expect("Cannot output file!"),
+        None => print!("{}", code),
+    }
 }
 
 ///
-/// Get terminals
+/// Import the rules of a grammar file of the other tools,
+/// and print them as the grammar of `opg`.
 ///
 /// ## Input
-/// `productions` the vector of struct `Production`
+/// `filename` the `.y` or `.g4` file.
 ///
-/// `nts` the non-terminal set, which could be generated 
-/// from the function `get_non_terminals()`. 
+/// `options` the options:
+/// - `--format bison|antlr`: the format, by the extension by default.
 ///
-/// ## Output
-/// The hashset contains terminals.
-/// 
 /// ## Example
 /// ```
-/// let nts = get_non_terminals(&productions);
-/// let ts = get_terminals(&productions, &nts);
+/// opg_import("expr.y", &[]);
 /// ```
 ///
 /// ## Principles
-/// To avoid repetative computing, receive the pre-computed
-/// non-terminal set and eliminate them among the candidates
-/// on the right side in each production.
+/// See `bison::import()` and `antlr::import()`.
 ///
-fn get_terminals(productions: &Vec<Production>, nts: &HashSet<String>) -> HashSet<String> {
-    let mut ts: HashSet<String> = HashSet::new();
-    for p in productions {
-        for v in p.right.iter() {
-            if !nts.contains(v) {
-                ts.insert(v.clone());
+fn opg_import(filename: &str, options: &[String]) {
+    let text = fs::read_to_string(filename).expect("No such file.");
+    let options = named_options(options, &["--format"]);
+    let extension = Path::new(filename).extension().and_then(|e| e.to_str());
+    let format = match (options.get("--format").map(|s| s.as_str()), extension) {
+        (Some(format), _) => format,
+        (None, Some("y" | "yy")) => "bison",
+        (None, Some("g4")) => "antlr",
+        _ => {
+            eprintln!("{}: unknown format, use --format bison|antlr", filename);
+            process::exit(1);
+        }
+    };
+    let productions = match format {
+        "bison" => bison::import(&text),
+        "antlr" => antlr::import(&text),
+        format => {
+            eprintln!("Unknown format: {}", format);
+            process::exit(1);
+        }
+    };
+    match productions {
+        Ok(productions) => print!("{}", format_grammar(&productions)),
+        Err(e) => {
+            eprintln!("{}: {}", filename, e);
+            process::exit(1);
+        }
+    }
+}
+
+///
+/// Write the JSON document of the grammar contents,
+/// or load a document and print its table.
+///
+/// ## Input
+/// `contents` The string read from file, the
+/// grammar or the document with `load`.
+///
+/// `output` the file to be written, or `None` to print.
+///
+/// `load` whether the contents is a document.
+///
+/// ## Example
+/// ```
+/// opg_json(&contents, Some("table.json"), false);
+/// ```
+///
+/// ## Principles
+/// See `json::document()` and `json::load()`.
+///
+fn opg_json(contents: &str, output: Option<&String>, load: bool) {
+    if load {
+        let (productions, table) = match json::load(contents) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
             }
+        };
+        print!("{}", format_grammar(&productions));
+        println!();
+        print!("{}", table);
+        for conflict in table.conflicts() {
+            println!("{}", table::format_conflict(conflict));
         }
+        return;
+    }
+    let productions: Vec<Production> = gen_productions(contents);
+    let document = format!("{}\n", json::document(&productions));
+    match output {
+        Some(file) => fs::write(file, document).expect("Cannot output file!"),
+        None => print!("{}", document),
     }
-    ts
 }
 
 ///
-/// Generate Operator Precedence Table
-/// for context-free grammar contents.
+/// Write the table of the grammar contents in the
+/// binary format, or load a file and print its table.
+///
+/// ## Input
+/// `filename` the grammar, or the binary file with `load`.
+///
+/// `output` the file to be written, `table.bin` by default.
+///
+/// `load` whether the file is in the binary format.
+///
+/// ## Example
+/// ```
+/// opg_binary("input1.txt", Some("table.bin"), false);
+/// ```
+///
+/// ## Principles
+/// See `binary::write()` and `binary::BinaryTable::load()`.
+///
+fn opg_binary(filename: &str, output: Option<&String>, load: bool) {
+    if load {
+        let bytes = fs::read(filename).expect("No such file.");
+        let table = match binary::BinaryTable::load(&bytes) {
+            Ok(table) => table,
+            Err(e) => {
+                eprintln!("{}: {}", filename, e);
+                process::exit(1);
+            }
+        };
+        print!("{}", table.to_table());
+        for (a, b, kept, rejected) in table.conflicts() {
+            println!("{}", table::format_conflict(&(a.to_string(), b.to_string(), kept, rejected)));
+        }
+        return;
+    }
+    let contents = fs::read_to_string(filename).expect("No such file.");
+    let productions: Vec<Production> = gen_productions(&contents);
+    let bytes = binary::write(&gen_table(&productions));
+    let output = output.map_or("table.bin", |s| s.as_str());
+    fs::write(output, &bytes).expect("Cannot output file!");
+    println!("Wrote {} bytes into {}.", bytes.len(), output);
+}
+
+///
+/// Print the sizes of the compressed representations
+/// of the operator precedence table.
 ///
 /// ## Input
 /// `contents` The string read from file.
 ///
 /// ## Example
 /// ```
-/// // Contents of the file
-/// let contents = fs::read_to_string(filename).expect("No such file.");
-/// // Get the table
-/// opg_generate(&contents);
+/// opg_compress(&contents);
 /// ```
 ///
 /// ## Principles
-/// Generate FIRSTVT and LASTVT for the contents.
-/// Then add S->$S$ for the starting non-terminal.
-/// Generate `OpTable` struct based on the algorithm
-/// of `find_eq()`, `find_less()`, `find_greater()`.
-/// Finally, print the `OpTable`.
+/// Measure the table by `compress::report()`, and print
+/// the compressed one chosen by `OpTable::compress()`,
+/// checked against the table cell by cell.
 ///
-fn opg_generate(contents: &String) {
-    let mut productions: Vec<Production> = gen_productions(&contents);
+fn opg_compress(contents: &str) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let table = gen_table(&productions);
     let nts = get_non_terminals(&productions);
-    let firstvt = gen_firstvt(&productions, &nts);
-    let lastvt = gen_lastvt(&productions, &nts);
+    let (_, mut tv) = ordered_symbols(&productions, &nts);
+    tv.push("$".to_string());
+    print!("{}", compress::report(&table, &tv));
+    println!();
+    // the merged row and column of every terminal
+    let maps = |rows: &[usize], cols: &[usize]| {
+        table::render(&["row".to_string(), "column".to_string()], &tv, |i, j| {
+            let k = tv.iter().position(|t| t == j).unwrap();
+            (if i == "row" { rows[k] } else { cols[k] }).to_string()
+        })
+    };
+    match table.compress(&tv) {
+        compress::Compressed::Functions(f, g) => {
+            println!("Precedence functions:");
+            print!("{}", table::render(&["f".to_string(), "g".to_string()], &tv, |i, j| {
+                let k = tv.iter().position(|t| t == j).unwrap();
+                (if i == "f" { f[k] } else { g[k] }).to_string()
+            }));
+        }
+        compress::Compressed::Merged(merged) => {
+            println!("Merged table:");
+            print!("{}", maps(&merged.rows, &merged.cols));
+            let rows: Vec<String> = (0..merged.height()).map(|r| r.to_string()).collect();
+            let cols: Vec<String> = (0..merged.width()).map(|c| c.to_string()).collect();
+            print!("{}", table::render(&rows, &cols, |i, j| {
+                let c = merged.matrix[i.parse::<usize>().unwrap()][j.parse::<usize>().unwrap()];
+                compress::relation(c).map_or(String::new(), |c| c.to_string())
+            }));
+            check_compressed(&table, &tv, &merged);
+        }
+        compress::Compressed::Packed(packed) => {
+            let list = |v: &[usize]| v.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(" ");
+            println!("Packed table:");
+            print!("{}", maps(&packed.rows, &packed.cols));
+            println!("base: {}", list(&packed.base));
+            println!("default: {}", list(&packed.default));
+            println!("slots: {}", list(&packed.slots));
+            check_compressed(&table, &tv, &packed);
+        }
+    }
+}
 
-    // add the $S$ for the starting non-terminal
-    let startnt = productions[0].left.clone();
-    productions.push(Production {
-        left: startnt.to_string(),
-        right: vec!["$".to_string(), startnt, "$".to_string()],
-    });
+///
+/// Check the compressed table against the table cell by cell.
+///
+fn check_compressed<T: table::Relations>(table: &table::OpTable, tv: &[String], compressed: &T) {
+    for a in tv.iter() {
+        for b in tv.iter() {
+            if compressed.get(a, b) != table.get(a, b) {
+                eprintln!("The compressed table differs at ({}, {}).", a, b);
+                process::exit(1);
+            }
+        }
+    }
+}
 
+///
+/// Convert the grammar contents into the Chomsky normal form
+/// and recognize a sentence with CYK.
+///
+/// ## Input
+/// `contents` The string read from file.
+///
+/// `sentence` the sentence to be recognized.
+///
+/// ## Example
+/// ```
+/// opg_cyk(&contents, "i + i * i");
+/// ```
+///
+/// ## Principles
+/// Print every step of `cnf::to_cnf()`, fill the table by
+/// `cyk::gen_cyk_table()` and print it. Then compare the decision
+/// with the operator precedence parser as an oracle. If the start
+/// symbol is unproductive, the grammar in CNF is empty and every
+/// sentence is rejected.
+///
+fn opg_cyk(contents: &str, sentence: &str) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let nts = get_non_terminals(&productions);
     let ts = get_terminals(&productions, &nts);
-    let mut table = table::OpTable::new(ts.clone());
+    let tokens = match lexer::tokenize(sentence, &ts) {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            eprintln!("{}: {}", sentence, e);
+            process::exit(1);
+        }
+    };
+    let kinds: Vec<String> = tokens[..tokens.len() - 1].iter().map(|t| t.kind.clone()).collect();
 
-    // if there is conflict on operator precedence,
-    // then the grammar is ambiguous.
-    find_eq(&mut table, &productions, &nts);
-    find_less(&mut table, &productions, &nts, &firstvt);
-    find_greater(&mut table, &productions, &nts, &lastvt);
+    let steps = cnf::to_cnf(&productions, &nts);
+    for (name, ps) in steps.iter() {
+        println!("{}:", name);
+        print!("{}", format_grammar(ps));
+        println!();
+    }
+    let cnf = &steps.last().unwrap().1;
+    if cnf.is_empty() {
+        println!("The start symbol is unproductive, the language is empty.");
+        println!();
+    }
+    let table = cyk::gen_cyk_table(cnf, &kinds);
+    print!("{}", cyk::format_cyk_table(&table, &kinds));
+    println!();
+    let accepted = cyk::accepts(cnf, &table, &kinds);
+    println!("CYK: {}", if accepted { "accepted" } else { "rejected" });
 
-    print!("{}", table);
-    fs::write("output.txt", &table.to_string()).expect("Cannot output file!");
+    let optable = gen_table(&productions);
+    if !optable.conflicts().is_empty() {
+        println!("OPG: the grammar is ambiguous, no decision.");
+        return;
+    }
+    let opg = parser::parse(&optable, &productions, &nts, &tokens, &mut parser::Recognizer);
+    match &opg {
+        Ok(_) => println!("OPG: accepted"),
+        Err(e) => println!("OPG: rejected ({})", e),
+    }
+    if opg.is_ok() == accepted {
+        println!("The decisions agree.");
+    } else {
+        println!("The decisions disagree.");
+    }
+}
+
+///
+/// Parse the options followed by a value,
+/// like `--lang rust`.
+///
+/// ## Input
+/// `options` the command line options.
+///
+/// `names` the allowed options.
+///
+/// ## Output
+/// The value of every given option.
+/// The process exits on an unknown option
+/// or a missing value.
+///
+fn named_options(options: &[String], names: &[&str]) -> HashMap<String, String> {
+    let mut values: HashMap<String, String> = HashMap::new();
+    let mut i = 0;
+    while i < options.len() {
+        if !names.contains(&options[i].as_str()) {
+            eprintln!("Unknown option: {}", options[i]);
+            process::exit(1);
+        }
+        match options.get(i + 1) {
+            Some(v) => values.insert(options[i].clone(), v.clone()),
+            None => {
+                eprintln!("Expected a value after {}", options[i]);
+                process::exit(1);
+            }
+        };
+        i += 2;
+    }
+    values
+}
+
+///
+/// Parse the options followed by a number,
+/// like `--count 5`, by `named_options()`.
+///
+fn number_options(options: &[String], names: &[&str]) -> HashMap<String, u64> {
+    let mut values: HashMap<String, u64> = HashMap::new();
+    for (name, v) in named_options(options, names) {
+        match v.parse::<u64>() {
+            Ok(v) => values.insert(name, v),
+            Err(_) => {
+                eprintln!("Expected a number after {}", name);
+                process::exit(1);
+            }
+        };
+    }
+    values
+}
+
+///
+/// Seed the random generator by the time.
+///
+fn time_seed() -> u64 {
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    now.map_or(1, |d| d.as_nanos() as u64)
+}
+
+///
+/// Generate the sentences of the grammar contents.
+///
+/// ## Input
+/// `contents` The string read from file.
+///
+/// `options` the options:
+/// - `--count N`: generate N random sentences, 10 by default.
+/// - `--depth N`: bound the height of the derivation trees, 10 by default.
+/// - `--seed N`: seed the random generator, the time by default.
+/// - `--forms STEPS`: generate the sentential forms by
+///   STEPS derivation steps at most instead.
+/// - `--all LENGTH`: enumerate all the sentences up to LENGTH tokens instead.
+///
+/// ## Example
+/// ```
+/// opg_sentences(&contents, &["--count".to_string(), "5".to_string()]);
+/// ```
+///
+/// ## Principles
+/// See `sentence::Generator` and `sentence::enumerate()`.
+/// The weights of the alternatives are given by
+/// `%weight` lines, see `gen_weights()`.
+///
+fn opg_sentences(contents: &str, options: &[String]) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let nts = get_non_terminals(&productions);
+    let options = number_options(options, &["--count", "--depth", "--seed", "--forms", "--all"]);
+    let count = options.get("--count").copied().unwrap_or(10);
+    let depth = options.get("--depth").copied().unwrap_or(10) as usize;
+    let seed = options.get("--seed").copied();
+    let forms = options.get("--forms").map(|v| *v as usize);
+    let all = options.get("--all").map(|v| *v as usize);
+
+    if let Some(length) = all {
+        for s in sentence::enumerate(&productions, &nts, length) {
+            println!("{}", s.join(" "));
+        }
+        return;
+    }
+    let mut generator = match sentence::Generator::new(&productions, &nts) {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    generator.weights = gen_weights(contents, &productions);
+    generator.depth = depth;
+    let mut rng = sentence::Rng::new(seed.unwrap_or_else(time_seed));
+    for _ in 0..count {
+        let s = match forms {
+            Some(steps) => generator.sentential_form(steps, &mut rng),
+            None => generator.sentence(&mut rng),
+        };
+        println!("{}", s.join(" "));
+    }
+}
+
+///
+/// Check the operator precedence table of the grammar
+/// contents with the generated sentences.
+///
+/// ## Input
+/// `contents` The string read from file.
+///
+/// `options` the options:
+/// - `--count N`: check N sentences and N mutations, 1000 by default.
+/// - `--depth N`: bound the height of the derivation trees, 10 by default.
+/// - `--seed N`: seed the random generator, the time by default.
+///
+/// ## Example
+/// ```
+/// opg_check(&contents, &["--seed".to_string(), "42".to_string()]);
+/// ```
+///
+/// ## Principles
+/// See `check::check()`. The seed is printed so that
+/// a failed check could be repeated, and the process
+/// exits with 1 if any counterexample is found.
+///
+fn opg_check(contents: &str, options: &[String]) {
+    let productions: Vec<Production> = gen_productions(contents);
+    let nts = get_non_terminals(&productions);
+    let table = gen_table(&productions);
+    if !table.conflicts().is_empty() {
+        eprintln!("The grammar is ambiguous.");
+        process::exit(1);
+    }
+    let options = number_options(options, &["--count", "--depth", "--seed"]);
+    let mut generator = match sentence::Generator::new(&productions, &nts) {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    generator.weights = gen_weights(contents, &productions);
+    generator.depth = options.get("--depth").copied().unwrap_or(10) as usize;
+    let count = options.get("--count").copied().unwrap_or(1000) as usize;
+    let seed = options.get("--seed").copied().unwrap_or_else(time_seed);
+
+    let mut rng = sentence::Rng::new(seed);
+    let report = check::check(&table, &productions, &nts, &generator, count, &mut rng);
+    println!(
+        "Checked {} sentences and {} mutations with seed {}.",
+        report.sentences, report.mutations, seed
+    );
+    let cases = [("rejected", &report.rejected), ("accepted", &report.accepted)];
+    for (kind, cases) in cases {
+        if cases.is_empty() {
+            continue;
+        }
+        println!("Wrongly {}: {}", kind, cases.len());
+        for (s, minimal) in cases {
+            println!("\t{}\t(minimal: {})", s.join(" "), minimal.join(" "));
+        }
+    }
+    if !report.rejected.is_empty() || !report.accepted.is_empty() {
+        process::exit(1);
+    }
+    println!("The table agrees with the grammar.");
+}
+
+///
+/// Transform the grammar contents
+/// and print the result in the file syntax.
+///
+/// ## Input
+/// `contents` The string read from file.
+///
+/// `passes` the transformations in order:
+/// - `--left-recursion`: `transform::eliminate_left_recursion()`.
+/// - `--left-factor`: `transform::left_factor()`.
+/// - `--useless`: `hygiene::remove_useless()`.
+/// - `--unit`: `transform::eliminate_unit()`.
+///
+/// ## Example
+/// ```
+/// opg_transform(&contents, &["--left-recursion".to_string()]);
+/// ```
+///
+/// ## Principles
+/// Run the passes one by one. The mapping from every new
+/// non-terminal to the original one is printed after the grammar
+/// as `%origin` directives, so the output could be read back,
+/// and the directives in the contents are kept.
+///
+fn opg_transform(contents: &str, passes: &[String]) {
+    let mut productions: Vec<Production> = gen_productions(contents);
+    // keep the mapping of a transformed grammar
+    let mut origins: Vec<(String, String)> = Vec::new();
+    for line in contents.lines() {
+        if let ["%origin", new, old] = line.split_whitespace().collect::<Vec<_>>()[..] {
+            origins.push((new.to_string(), old.to_string()));
+        }
+    }
+    for pass in passes {
+        let (ps, news) = match pass.as_str() {
+            "--left-recursion" => transform::eliminate_left_recursion(&productions),
+            "--left-factor" => transform::left_factor(&productions),
+            "--useless" => (hygiene::remove_useless(&productions), Vec::new()),
+            "--unit" => transform::eliminate_unit(&productions),
+            _ => {
+                eprintln!("Unknown transformation: {}", pass);
+                process::exit(1);
+            }
+        };
+        productions = ps;
+        for (new, old) in news {
+            // map back to the original non-terminal
+            let old = match origins.iter().find(|(n, _)| *n == old) {
+                Some((_, o)) => o.clone(),
+                None => old,
+            };
+            origins.push((new, old));
+        }
+    }
+    print!("{}", format_grammar(&productions));
+    print!("{}", transform::format_origins(&origins));
 }
 
 fn main() {
@@ -430,9 +1232,156 @@ fn main() {
     if args.len() < 2 {
         panic!("Please follow a file name!");
     }
-    let filename = &args[1];
-    // Contents of the file
-    let contents = fs::read_to_string(filename).expect("No such file.");
-    // Get the table
-    opg_generate(&contents);
+    match args[1].as_str() {
+        "binary" => {
+            if args.len() < 3 || (args[2] == "--load" && args.len() < 4) {
+                panic!("Usage: opg binary <grammar> [--output FILE] | opg binary --load <file>");
+            }
+            if args[2] == "--load" {
+                opg_binary(&args[3], None, true);
+            } else {
+                let options = named_options(&args[3..], &["--output"]);
+                opg_binary(&args[2], options.get("--output"), false);
+            }
+        }
+        "check" => {
+            if args.len() < 3 {
+                panic!("Usage: opg check <grammar> [--count N] [--depth N] [--seed N]");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_check(&contents, &args[3..]);
+        }
+        "codegen" => {
+            if args.len() < 3 {
+                panic!(
+                    "Usage: opg codegen <grammar> [--lang rust|c] [--output BASE] [--prefix NAME] [--layout dense|functions|merged|packed]"
+                );
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_codegen(&contents, &args[3..]);
+        }
+        "compress" => {
+            if args.len() < 3 {
+                panic!("Usage: opg compress <grammar>");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_compress(&contents);
+        }
+        "cyk" => {
+            if args.len() < 4 {
+                panic!("Usage: opg cyk <grammar> <sentence>");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_cyk(&contents, &args[3]);
+        }
+        "derive" => {
+            if args.len() < 4 {
+                panic!("Usage: opg derive <grammar> <sentence> [--latex]");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_derive(&contents, &args[3], args[4..].iter().any(|a| a == "--latex"));
+        }
+        "earley" => {
+            if args.len() < 4 {
+                panic!("Usage: opg earley <grammar> <sentence>");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_earley(&contents, &args[3]);
+        }
+        "eval" => {
+            if args.len() < 4 {
+                panic!("Usage: opg eval <grammar> <expression>...");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_eval(&contents, &args[3..]);
+        }
+        "export" => {
+            if args.len() < 3 {
+                panic!("Usage: opg export <grammar> [--format bison|antlr] [--output FILE] [--name NAME]");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_export(&contents, &args[3..]);
+        }
+        "generate" => {
+            if args.len() < 3 {
+                panic!("Usage: opg generate <grammar> [--count N] [--depth N] [--seed N] [--forms STEPS] [--all LENGTH]");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_sentences(&contents, &args[3..]);
+        }
+        "hygiene" => {
+            if args.len() < 3 {
+                panic!("Usage: opg hygiene <grammar>");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_hygiene(&contents);
+        }
+        "import" => {
+            if args.len() < 3 {
+                panic!("Usage: opg import <file> [--format bison|antlr]");
+            }
+            opg_import(&args[2], &args[3..]);
+        }
+        "json" => {
+            if args.len() < 3 || (args[2] == "--load" && args.len() < 4) {
+                panic!("Usage: opg json <grammar> [--output FILE] | opg json --load <document>");
+            }
+            if args[2] == "--load" {
+                let contents = fs::read_to_string(&args[3]).expect("No such file.");
+                opg_json(&contents, None, true);
+            } else {
+                let contents = fs::read_to_string(&args[2]).expect("No such file.");
+                let options = named_options(&args[3..], &["--output"]);
+                opg_json(&contents, options.get("--output"), false);
+            }
+        }
+        "ll1" => {
+            if args.len() < 3 {
+                panic!("Usage: opg ll1 <grammar>");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_ll1(&contents);
+        }
+        "lr0" | "slr" | "lr1" | "lalr" => {
+            if args.len() < 3 {
+                panic!("Usage: opg {} <grammar> [--prec]", args[1]);
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_lr(&contents, &args[1], args[3..].iter().any(|a| a == "--prec"));
+        }
+        "pratt" => {
+            if args.len() < 3 {
+                panic!("Usage: opg pratt <grammar> [--skeleton] [--output FILE]");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_pratt(&contents, &args[3..]);
+        }
+        "simple" => {
+            if args.len() < 3 {
+                panic!("Usage: opg simple <grammar> [sentence]");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_simple(&contents, args.get(3).map(|s| s.as_str()));
+        }
+        "transform" => {
+            if args.len() < 4 {
+                panic!("Usage: opg transform <grammar> <pass>...");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_transform(&contents, &args[3..]);
+        }
+        "translate" => {
+            if args.len() < 4 {
+                panic!("Usage: opg translate <grammar> <expression>...");
+            }
+            let contents = fs::read_to_string(&args[2]).expect("No such file.");
+            opg_translate(&contents, &args[3..]);
+        }
+        filename => {
+            // Contents of the file
+            let contents = fs::read_to_string(filename).expect("No such file.");
+            // Get the table
+            opg_generate(&contents);
+        }
+    }
 }
@@ -0,0 +1,206 @@
+//! # parser
+//!
+//! `parser` runs the operator precedence
+//! parsing on an `OpTable` and hands
+//! every reduction to a `Reducer`.
+
+use std::collections::HashSet;
+
+use crate::lexer::Token;
+use crate::table::Relations;
+use crate::Production;
+
+///
+/// The semantic side of the parsing.
+///
+/// `shift()` gives the value of a token,
+/// and `reduce()` gives the value of the left side
+/// from the values of the prime phrase.
+///
+pub trait Reducer {
+    type Value;
+
+    fn shift(&mut self, token: &Token) -> Result<Self::Value, String>;
+
+    fn reduce(
+        &mut self,
+        production: &Production,
+        children: Vec<Self::Value>,
+    ) -> Result<Self::Value, String>;
+}
+
+///
+/// A `Reducer` which only accepts or rejects.
+///
+pub struct Recognizer;
+
+impl Reducer for Recognizer {
+    type Value = ();
+
+    fn shift(&mut self, _token: &Token) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn reduce(&mut self, _production: &Production, _children: Vec<()>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+///
+/// An element in the parsing stack.
+///
+struct Entry<V> {
+    symbol: String,
+    terminal: bool,
+    value: Option<V>,
+}
+
+///
+/// Find the production for a prime phrase.
+///
+/// ## Principles
+/// Operator precedence parsing does not tell
+/// non-terminals apart, so a production matches
+/// if the terminals are the same and the non-terminals
+/// are at the same positions. The production whose
+/// non-terminals are exactly the same is preferred.
+///
+fn find_production<'a, V>(
+    productions: &'a [Production],
+    nts: &HashSet<String>,
+    phrase: &[Entry<V>],
+) -> Option<&'a Production> {
+    let mut candidates = productions.iter().filter(|p| {
+        p.right.len() == phrase.len()
+            && p.right.iter().zip(phrase.iter()).all(|(r, e)| {
+                if e.terminal {
+                    *r == e.symbol
+                } else {
+                    nts.contains(r)
+                }
+            })
+    });
+    let first = candidates.next();
+    first
+        .into_iter()
+        .chain(candidates)
+        .find(|p| p.right.iter().zip(phrase.iter()).all(|(r, e)| *r == e.symbol))
+        .or(first)
+}
+
+///
+/// Parse the tokens with the operator precedence table.
+///
+/// ## Input
+/// `table` the `OpTable` of the grammar, or
+/// any table with the `Relations`, like `binary::BinaryTable`.
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `tokens` the input ended with `$`.
+///
+/// `reducer` the semantic side of the parsing.
+///
+/// ## Return
+/// The value of the starting non-terminal.
+///
+/// ## Example
+/// ```
+/// let tokens = lexer::tokenize("1 + 2 * 3", table.terminals())?;
+/// let value = parser::parse(&table, &productions, &nts, &tokens, &mut semantic::Evaluator)?;
+/// ```
+///
+/// ## Principles
+/// Compare the topmost terminal in the stack with the input:
+/// - `<` or `=`: shift the input.
+/// - `>`: go down the stack until a `<` is met, then
+///   reduce the prime phrase above it by `find_production()`.
+///
+/// Single non-terminal productions like `E -> T`
+/// never show up as a prime phrase, so they are not reduced.
+///
+pub fn parse<T: Relations, R: Reducer>(
+    table: &T,
+    productions: &[Production],
+    nts: &HashSet<String>,
+    tokens: &[Token],
+    reducer: &mut R,
+) -> Result<R::Value, String> {
+    let mut stack: Vec<Entry<R::Value>> = vec![Entry {
+        symbol: "$".to_string(),
+        terminal: true,
+        value: None,
+    }];
+    let end = Token::new("$");
+    let mut ip = 0;
+    loop {
+        let token = tokens.get(ip).unwrap_or(&end);
+        let a = token.kind.as_str();
+        if !table.contains(a) {
+            return Err(format!("unknown terminal '{}'", token.text));
+        }
+        // the topmost terminal
+        let mut j = stack.len() - 1;
+        if !stack[j].terminal {
+            j -= 1;
+        }
+        if stack[j].symbol == "$" && a == "$" {
+            if stack.len() == 2 {
+                return Ok(stack.pop().unwrap().value.unwrap());
+            }
+            return Err("unexpected end of input".to_string());
+        }
+        match table.get(&stack[j].symbol, a) {
+            Some('<') | Some('=') => {
+                stack.push(Entry {
+                    symbol: a.to_string(),
+                    terminal: true,
+                    value: Some(reducer.shift(token)?),
+                });
+                ip += 1;
+            }
+            Some('>') => {
+                // find the head of the prime phrase
+                let start = loop {
+                    if j == 0 {
+                        return Err(format!("no prime phrase before '{}'", token.text));
+                    }
+                    let mut k = j - 1;
+                    if !stack[k].terminal {
+                        if k == 0 {
+                            return Err(format!("no prime phrase before '{}'", token.text));
+                        }
+                        k -= 1;
+                    }
+                    if table.get(&stack[k].symbol, &stack[j].symbol) == Some('<') {
+                        break k + 1;
+                    }
+                    j = k;
+                };
+                let phrase = stack.split_off(start);
+                let production = match find_production(productions, nts, &phrase) {
+                    Some(p) => p,
+                    None => {
+                        let symbols: Vec<&str> = phrase.iter().map(|e| e.symbol.as_str()).collect();
+                        return Err(format!("no production for '{}'", symbols.join(" ")));
+                    }
+                };
+                let children = phrase.into_iter().map(|e| e.value.unwrap()).collect();
+                stack.push(Entry {
+                    symbol: production.left.clone(),
+                    terminal: false,
+                    value: Some(reducer.reduce(production, children)?),
+                });
+            }
+            _ => {
+                return Err(format!(
+                    "no relation between '{}' and '{}'",
+                    stack[j].symbol, token.text
+                ))
+            }
+        }
+    }
+}
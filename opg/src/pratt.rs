@@ -0,0 +1,363 @@
+//! # pratt
+//!
+//! `pratt` infers the binding powers of the operators
+//! from an `OpTable`, and emits the skeleton of a Pratt
+//! parser, i.e. a precedence climbing parser, in Rust.
+
+use std::collections::HashSet;
+
+use crate::table::OpTable;
+use crate::{format_grammar, Assoc, Production};
+
+///
+/// The position of an operator to its operands.
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Fixity {
+    /// `A -> op B`
+    Prefix,
+    /// `A -> B op C`
+    Infix,
+    /// `A -> B op`
+    Postfix,
+}
+
+///
+/// An operator with its binding powers.
+///
+pub struct Operator {
+    pub name: String,
+    pub fixity: Fixity,
+    /// the power binding the operand on the left,
+    /// `None` for a prefix operator.
+    pub left: Option<usize>,
+    /// the power binding the operand on the right,
+    /// `None` for a postfix operator.
+    pub right: Option<usize>,
+    /// the associativity of an infix operator.
+    pub assoc: Option<Assoc>,
+}
+
+///
+/// The binding power model of a grammar.
+///
+#[derive(Default)]
+pub struct Powers {
+    pub operators: Vec<Operator>,
+    /// the terminals as a whole operand, like `i`.
+    pub atoms: Vec<String>,
+    /// the brackets as `(open, close)`, like `( E )`.
+    pub groups: Vec<(String, String)>,
+    /// the reasons why some relations do not fit.
+    pub misfits: Vec<String>,
+}
+
+fn push_new<T: PartialEq>(v: &mut Vec<T>, x: T) {
+    if !v.contains(&x) {
+        v.push(x);
+    }
+}
+
+type Shapes = (Vec<(String, Fixity)>, Vec<String>, Vec<(String, String)>, Vec<usize>);
+
+///
+/// Classify the terminals by the shapes
+/// of the productions using them.
+///
+/// ## Return
+/// The operators, the atoms, the groups, and
+/// the productions of the other shapes.
+///
+fn classify(productions: &[Production], nts: &HashSet<String>) -> Shapes {
+    let (mut operators, mut atoms, mut groups, mut others) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (k, p) in productions.iter().enumerate() {
+        let shape: Vec<bool> = p.right.iter().map(|v| nts.contains(v)).collect();
+        let r = &p.right;
+        match shape.as_slice() {
+            [] | [true] => {}
+            [false] => push_new(&mut atoms, r[0].clone()),
+            [false, true, false] => push_new(&mut groups, (r[0].clone(), r[2].clone())),
+            [true, false, true] => push_new(&mut operators, (r[1].clone(), Fixity::Infix)),
+            [false, true] => push_new(&mut operators, (r[0].clone(), Fixity::Prefix)),
+            [true, false] => push_new(&mut operators, (r[1].clone(), Fixity::Postfix)),
+            _ => others.push(k),
+        }
+    }
+    (operators, atoms, groups, others)
+}
+
+///
+/// Infer the binding powers of the operators.
+///
+/// ## Input
+/// `table` the `OpTable` of the grammar.
+///
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `tv` the terminals in order.
+///
+/// ## Return
+/// The `Powers`, where `misfits` is empty if the
+/// Pratt parser accepts the same language as the table.
+///
+/// ## Example
+/// ```
+/// let powers = pratt::infer(&table, &productions, &nts, &tv);
+/// print!("{}", pratt::format_powers(&powers));
+/// ```
+///
+/// ## Principles
+/// 1. An operator is a terminal in a production like
+///    `A -> op B`, `A -> B op C` or `A -> B op`.
+/// 2. The Pratt parser parsing the right operand of `a`
+///    with the minimal power `right(a)` consumes `b` iff
+///    `left(b) > right(a)`, as the table shifts `b` iff
+///    `a < b`. So `right(a) = f(a) + 1` and `left(b) = g(b) + 1`
+///    for the precedence functions f and g of the operators,
+///    see `OpTable::gen_functions()`, and 0 is left for the start.
+/// 3. The relation of an infix operator to itself gives the
+///    associativity: `>` is left, `<` is right and none is
+///    non-associative.
+/// 4. It does not fit if `a` taking a right operand and `b`
+///    taking a left operand are not related by `<` or `>`,
+///    or if the precedence functions do not exist. The
+///    terminals in the productions of other shapes do not
+///    fit either, like the ternary operator `A -> B ? C : D`.
+///
+pub fn infer(table: &OpTable, productions: &[Production], nts: &HashSet<String>, tv: &[String]) -> Powers {
+    let (mut shapes, atoms, groups, others) = classify(productions, nts);
+    let rank = |t: &String| tv.iter().position(|v| v == t).unwrap_or(tv.len());
+    shapes.sort_by_key(|(t, fixity)| (rank(t), *fixity as usize));
+    let mut powers = Powers {
+        atoms,
+        groups,
+        ..Powers::default()
+    };
+
+    for k in others {
+        powers.misfits.push(format!(
+            "'{}' is not the production of an operator, an atom or a group",
+            productions[k]
+        ));
+    }
+    let takes = |t: &str, fixities: [Fixity; 2]| shapes.iter().any(|(v, f)| v == t && fixities.contains(f));
+    let mut order: Vec<String> = Vec::new();
+    for (t, _) in shapes.iter() {
+        push_new(&mut order, t.clone());
+    }
+    for a in order.iter().filter(|a| takes(a, [Fixity::Prefix, Fixity::Infix])) {
+        for b in order.iter().filter(|b| takes(b, [Fixity::Infix, Fixity::Postfix])) {
+            match table.get(a, b) {
+                Some('=') => powers
+                    .misfits
+                    .push(format!("'{}' and '{}' are equal like the parts of one operator", a, b)),
+                None if a == b && shapes.contains(&(a.clone(), Fixity::Infix)) => {}
+                None => powers
+                    .misfits
+                    .push(format!("'{}' followed by '{}' is an error, which has no binding powers", a, b)),
+                _ => {}
+            }
+        }
+    }
+
+    // drop the operator in a cycle of the relations,
+    // so that the others still have the binding powers
+    let mut functions = table.gen_functions(&order);
+    if functions.is_none() {
+        let culprit = (0..order.len()).find(|i| {
+            let mut rest = order.clone();
+            rest.remove(*i);
+            table.gen_functions(&rest).is_some()
+        });
+        match culprit {
+            Some(i) => {
+                let t = order.remove(i);
+                powers
+                    .misfits
+                    .push(format!("'{}' is in a cycle of the relations, which has no binding powers", t));
+                functions = table.gen_functions(&order);
+            }
+            None => powers
+                .misfits
+                .push("the relations of the operators are in cycles, which have no binding powers".to_string()),
+        }
+    }
+
+    for (t, fixity) in shapes {
+        let i = order.iter().position(|v| *v == t);
+        let fg = functions.as_ref().zip(i).map(|((f, g), i)| (f[i] + 1, g[i] + 1));
+        let assoc = match (fixity, table.get(&t, &t)) {
+            (Fixity::Infix, Some('>')) => Some(Assoc::Left),
+            (Fixity::Infix, Some('<')) => Some(Assoc::Right),
+            (Fixity::Infix, None) => Some(Assoc::Nonassoc),
+            _ => None,
+        };
+        powers.operators.push(Operator {
+            left: fg.filter(|_| fixity != Fixity::Prefix).map(|(_, g)| g),
+            right: fg.filter(|_| fixity != Fixity::Postfix).map(|(f, _)| f),
+            name: t,
+            fixity,
+            assoc,
+        });
+    }
+    powers
+}
+
+///
+/// Output the binding powers as a table in the
+/// format of `OpTable`, where `-` is not applicable.
+///
+pub fn format_powers(powers: &Powers) -> String {
+    let mut output = String::from(" \tfixity\tleft\tright\tassoc\t\n");
+    let power = |p: Option<usize>| p.map_or("-".to_string(), |p| p.to_string());
+    for o in powers.operators.iter() {
+        let fixity = match o.fixity {
+            Fixity::Prefix => "prefix",
+            Fixity::Infix => "infix",
+            Fixity::Postfix => "postfix",
+        };
+        let assoc = match o.assoc {
+            Some(Assoc::Left) => "left",
+            Some(Assoc::Right) => "right",
+            Some(Assoc::Nonassoc) => "nonassoc",
+            None => "-",
+        };
+        output += &format!(
+            "{}\t{}\t{}\t{}\t{}\t\n",
+            o.name,
+            fixity,
+            power(o.left),
+            power(o.right),
+            assoc
+        );
+    }
+    output
+}
+
+///
+/// The arms of a `match` from the operators of the fixity.
+///
+fn power_arms(powers: &Powers, fixity: Fixity) -> String {
+    let mut output = String::new();
+    for o in powers.operators.iter().filter(|o| o.fixity == fixity) {
+        let value = match (o.left, o.right) {
+            (Some(l), Some(r)) => format!("({}, {})", l, r),
+            (Some(p), None) | (None, Some(p)) => p.to_string(),
+            (None, None) => continue,
+        };
+        output += &format!("        {:?} => Some({}),\n", o.name, value);
+    }
+    output
+}
+
+///
+/// Generate the skeleton of a Pratt parser in Rust.
+///
+/// ## Return
+/// The source code, which parses a slice of terminals
+/// into the syntax tree `Expr`, to be replaced by the
+/// semantic values.
+///
+/// ## Example
+/// ```
+/// let powers = pratt::infer(&table, &productions, &nts, &tv);
+/// fs::write("pratt.rs", pratt::skeleton(&powers, &productions))?;
+/// ```
+///
+/// ## Principles
+/// The operands start with an atom, a group or a prefix
+/// operator, then the loop consumes the postfix and infix
+/// operators binding tighter than the minimal power. A
+/// non-associative operator is an error next to itself.
+/// The operators which do not fit are left out.
+///
+pub fn skeleton(powers: &Powers, productions: &[Production]) -> String {
+    let mut output = String::from("// A Pratt parser generated by opg for the grammar\n//\n");
+    for line in format_grammar(productions).lines() {
+        output += &format!("// {}\n", line);
+    }
+    output += "//\n// with the binding powers inferred from the operator precedence table.\n";
+    output += "// Replace `Expr` with the own syntax tree or semantic values.\n\n";
+
+    output += "#[derive(Debug)]\npub enum Expr {\n    Atom(String),\n    Prefix(String, Box<Expr>),\n";
+    output += "    Infix(Box<Expr>, String, Box<Expr>),\n    Postfix(Box<Expr>, String),\n}\n\n";
+
+    for (doc, name, ty, fixity) in [
+        ("The right binding power of a prefix operator.", "prefix", "u32", Fixity::Prefix),
+        ("The left and right binding powers of an infix operator.", "infix", "(u32, u32)", Fixity::Infix),
+        ("The left binding power of a postfix operator.", "postfix", "u32", Fixity::Postfix),
+    ] {
+        let arms = power_arms(powers, fixity);
+        if arms.is_empty() {
+            output += &format!("/// {}\npub fn {}_power(_op: &str) -> Option<{}> {{\n    None\n}}\n\n", doc, name, ty);
+            continue;
+        }
+        output += &format!("/// {}\npub fn {}_power(op: &str) -> Option<{}> {{\n", doc, name, ty);
+        output += &format!("    match op {{\n{}        _ => None,\n    }}\n}}\n\n", arms);
+    }
+
+    let nonassoc: Vec<String> = powers
+        .operators
+        .iter()
+        .filter(|o| o.assoc == Some(Assoc::Nonassoc) && o.left.is_some())
+        .map(|o| format!("{:?}", o.name))
+        .collect();
+    output += "/// Whether an infix operator is non-associative.\n";
+    if nonassoc.is_empty() {
+        output += "fn nonassoc(_op: &str) -> bool {\n    false\n}\n\n";
+    } else {
+        output += &format!("fn nonassoc(op: &str) -> bool {{\n    matches!(op, {})\n}}\n\n", nonassoc.join(" | "));
+    }
+
+    output += "/// Parse the terminals, without `$`.\n";
+    output += "pub fn parse(tokens: &[&str]) -> Result<Expr, String> {\n";
+    output += "    let mut pos = 0;\n    let expr = expr(tokens, &mut pos, 0, None)?;\n";
+    output += "    match tokens.get(pos) {\n";
+    output += "        Some(t) => Err(format!(\"unexpected '{}' at {}\", t, pos)),\n";
+    output += "        None => Ok(expr),\n    }\n}\n\n";
+
+    if !powers.groups.is_empty() {
+        output += "fn expect(tokens: &[&str], pos: &mut usize, t: &str) -> Result<(), String> {\n";
+        output += "    if tokens.get(*pos) != Some(&t) {\n";
+        output += "        return Err(format!(\"expected '{}' at {}\", t, *pos));\n    }\n";
+        output += "    *pos += 1;\n    Ok(())\n}\n\n";
+    }
+
+    output += "/// Parse an expression whose operators bind tighter than `min`,\n";
+    output += "/// as the right operand of `parent`.\n";
+    output += "fn expr(tokens: &[&str], pos: &mut usize, min: u32, parent: Option<&str>) -> Result<Expr, String> {\n";
+    output += "    let token = *tokens.get(*pos).ok_or(\"unexpected end of input\")?;\n";
+    output += "    *pos += 1;\n    let mut lhs = match token {\n";
+    if !powers.atoms.is_empty() {
+        let atoms: Vec<String> = powers.atoms.iter().map(|t| format!("{:?}", t)).collect();
+        output += &format!("        {} => Expr::Atom(token.to_string()),\n", atoms.join(" | "));
+    }
+    for (open, close) in powers.groups.iter() {
+        output += &format!("        {:?} => {{\n", open);
+        output += "            let e = expr(tokens, pos, 0, None)?;\n";
+        output += &format!("            expect(tokens, pos, {:?})?;\n            e\n        }}\n", close);
+    }
+    output += "        _ => match prefix_power(token) {\n";
+    output += "            Some(r) => Expr::Prefix(token.to_string(), Box::new(expr(tokens, pos, r, Some(token))?)),\n";
+    output += "            None => return Err(format!(\"unexpected '{}' at {}\", token, *pos - 1)),\n";
+    output += "        },\n    };\n";
+    output += "    while let Some(&op) = tokens.get(*pos) {\n";
+    output += "        if let Some(l) = postfix_power(op) {\n";
+    output += "            if l <= min {\n                break;\n            }\n";
+    output += "            *pos += 1;\n            lhs = Expr::Postfix(Box::new(lhs), op.to_string());\n";
+    output += "            continue;\n        }\n";
+    output += "        let (l, r) = match infix_power(op) {\n";
+    output += "            Some(p) => p,\n            None => break,\n        };\n";
+    output += "        if l <= min {\n            break;\n        }\n";
+    output += "        if nonassoc(op) && parent == Some(op) {\n";
+    output += "            return Err(format!(\"'{}' is non-associative at {}\", op, *pos));\n        }\n";
+    output += "        *pos += 1;\n        let rhs = expr(tokens, pos, r, Some(op))?;\n";
+    output += "        lhs = Expr::Infix(Box::new(lhs), op.to_string(), Box::new(rhs));\n";
+    output += "        if nonassoc(op) && tokens.get(*pos) == Some(&op) {\n";
+    output += "            return Err(format!(\"'{}' is non-associative at {}\", op, *pos));\n        }\n";
+    output += "    }\n    Ok(lhs)\n}\n";
+    output
+}
@@ -0,0 +1,228 @@
+//! # quad
+//!
+//! `quad` translates the reductions of the
+//! operator precedence parsing into quadruples
+//! `(op, arg1, arg2, result)` and three-address code.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::lexer::Token;
+use crate::parser::Reducer;
+use crate::Production;
+
+///
+/// A struct to represent a quadruple.
+///
+/// An empty field is written as `_`.
+///
+pub struct Quadruple {
+    pub op: String,
+    pub arg1: String,
+    pub arg2: String,
+    pub result: String,
+}
+
+impl Quadruple {
+    ///
+    /// Convert the quadruple to three-address code.
+    ///
+    /// ## Principles
+    /// - `(=, a, _, x)` is `x = a`.
+    /// - `(op, a, _, x)` is `x = op a`.
+    /// - `(op, a, b, x)` is `x = a op b`.
+    ///
+    pub fn to_tac(&self) -> String {
+        if self.op == "=" || self.op == ":=" {
+            format!("{} = {}", self.result, self.arg1)
+        } else if self.arg2 == "_" {
+            format!("{} = {} {}", self.result, self.op, self.arg1)
+        } else {
+            format!("{} = {} {} {}", self.result, self.arg1, self.op, self.arg2)
+        }
+    }
+}
+
+impl Display for Quadruple {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "({}, {}, {}, {})", self.op, self.arg1, self.arg2, self.result)
+    }
+}
+
+///
+/// The place of a symbol during translation.
+///
+#[derive(Clone)]
+pub enum Place {
+    /// a name, a literal or a temporary holding the value.
+    Addr(String),
+    /// an operator or a delimiter.
+    Token(String),
+}
+
+impl Place {
+    fn text(&self) -> &str {
+        match self {
+            Place::Addr(s) | Place::Token(s) => s,
+        }
+    }
+}
+
+///
+/// A `Reducer` which emits quadruples.
+///
+pub struct Translator {
+    /// the terminal standing for operands.
+    operand: Option<String>,
+    /// the number of temporaries used.
+    temps: usize,
+    /// the quadruples emitted.
+    pub quads: Vec<Quadruple>,
+}
+
+impl Translator {
+    ///
+    /// Create a new `Translator`.
+    ///
+    /// `operand` is the terminal whose tokens are addresses,
+    /// which could be found by `lexer::operand_terminal()`.
+    ///
+    pub fn new(operand: Option<String>) -> Translator {
+        Translator {
+            operand,
+            temps: 0,
+            quads: Vec::new(),
+        }
+    }
+
+    fn new_temp(&mut self) -> String {
+        self.temps += 1;
+        format!("t{}", self.temps)
+    }
+
+    ///
+    /// Emit the quadruples by the templates.
+    ///
+    /// ## Principles
+    /// Every template has four fields split by ",".
+    /// `$n` is the place of the n-th symbol,
+    /// `$$` is a fresh temporary shared by the templates
+    /// of the production, and others are kept as they are.
+    /// The place of the left side is the result of
+    /// the last template.
+    ///
+    fn emit_templates(&mut self, production: &Production, children: &[Place]) -> Result<Place, String> {
+        let mut temp: Option<String> = None;
+        let mut place = String::new();
+        for template in production.templates.iter() {
+            let mut fields: Vec<String> = Vec::new();
+            for field in template.split(',').map(|f| f.trim()) {
+                let value = if field == "$$" {
+                    if temp.is_none() {
+                        temp = Some(self.new_temp());
+                    }
+                    temp.clone().unwrap()
+                } else if let Some(n) = field.strip_prefix('$') {
+                    let i: usize = n.parse().map_err(|_| format!("bad field '{}' in template", field))?;
+                    match i.checked_sub(1).and_then(|i| children.get(i)) {
+                        Some(p) => p.text().to_string(),
+                        None => return Err(format!("{} is out of the production", field)),
+                    }
+                } else if field.is_empty() {
+                    "_".to_string()
+                } else {
+                    field.to_string()
+                };
+                fields.push(value);
+            }
+            if fields.len() != 4 {
+                return Err(format!("template '{}' should have 4 fields", template));
+            }
+            place = fields[3].clone();
+            let mut fields = fields.into_iter();
+            self.quads.push(Quadruple {
+                op: fields.next().unwrap(),
+                arg1: fields.next().unwrap(),
+                arg2: fields.next().unwrap(),
+                result: fields.next().unwrap(),
+            });
+        }
+        Ok(Place::Addr(place))
+    }
+
+    ///
+    /// The built-in translation for a production without templates.
+    ///
+    /// ## Principles
+    /// - `N op N`: emit `(op, N, N, t)`.
+    /// - `op N`: emit `(op, N, _, t)`.
+    /// - A single address in the right side, like
+    ///   `F -> ( E )` or `F -> i`: pass it to the left side.
+    ///
+    fn default_translation(&mut self, production: &Production, children: &[Place]) -> Result<Place, String> {
+        let (op, arg1, arg2) = match children {
+            [Place::Addr(a), Place::Token(op), Place::Addr(b)] => (op, a.clone(), b.clone()),
+            [Place::Token(op), Place::Addr(a)] => (op, a.clone(), "_".to_string()),
+            _ => {
+                let mut addrs = children.iter().filter(|p| matches!(p, Place::Addr(_)));
+                return match (addrs.next(), addrs.next()) {
+                    (Some(p), None) => Ok(p.clone()),
+                    _ => Err(format!(
+                        "no template for {} -> {}",
+                        production.left,
+                        production.right.join(" ")
+                    )),
+                };
+            }
+        };
+        let result = self.new_temp();
+        self.quads.push(Quadruple {
+            op: op.clone(),
+            arg1,
+            arg2,
+            result: result.clone(),
+        });
+        Ok(Place::Addr(result))
+    }
+}
+
+impl Reducer for Translator {
+    type Value = Place;
+
+    fn shift(&mut self, token: &Token) -> Result<Place, String> {
+        if Some(&token.kind) == self.operand.as_ref() {
+            Ok(Place::Addr(token.text.clone()))
+        } else {
+            Ok(Place::Token(token.text.clone()))
+        }
+    }
+
+    fn reduce(&mut self, production: &Production, children: Vec<Place>) -> Result<Place, String> {
+        if production.templates.is_empty() {
+            self.default_translation(production, &children)
+        } else {
+            self.emit_templates(production, &children)
+        }
+    }
+}
+
+///
+/// Format the quadruples as a table.
+///
+pub fn quad_table(quads: &[Quadruple]) -> String {
+    let mut output = String::from("No.\top\targ1\targ2\tresult\n");
+    for (i, q) in quads.iter().enumerate() {
+        output += &format!("{}\t{}\t{}\t{}\t{}\n", i, q.op, q.arg1, q.arg2, q.result);
+    }
+    output
+}
+
+///
+/// Format the quadruples as three-address code.
+///
+pub fn tac(quads: &[Quadruple]) -> String {
+    let mut output = String::new();
+    for (i, q) in quads.iter().enumerate() {
+        output += &format!("({})\t{}\n", i, q.to_tac());
+    }
+    output
+}
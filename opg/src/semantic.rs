@@ -0,0 +1,257 @@
+//! # semantic
+//!
+//! `semantic` executes the semantic actions
+//! of the productions, like `{ $1 + $3 }`,
+//! to evaluate integer and float expressions.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::lexer::Token;
+use crate::parser::Reducer;
+use crate::Production;
+
+///
+/// The value of a symbol during evaluation.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    /// the text of a non-numeric token, like an operator.
+    Text(String),
+}
+
+impl Value {
+    fn as_float(&self) -> Result<f64, String> {
+        match self {
+            Value::Int(i) => Ok(*i as f64),
+            Value::Float(f) => Ok(*f),
+            Value::Text(t) => Err(format!("'{}' is not a number", t)),
+        }
+    }
+
+    ///
+    /// Apply a binary arithmetic operator.
+    ///
+    /// ## Principles
+    /// Two integers give an integer,
+    /// otherwise both are promoted to float.
+    ///
+    pub fn binary(op: &str, l: &Value, r: &Value) -> Result<Value, String> {
+        if let (Value::Int(a), Value::Int(b)) = (l, r) {
+            let v = match op {
+                "+" => a.checked_add(*b),
+                "-" => a.checked_sub(*b),
+                "*" => a.checked_mul(*b),
+                "/" | "%" if *b == 0 => return Err("division by zero".to_string()),
+                "/" => a.checked_div(*b),
+                "%" => a.checked_rem(*b),
+                _ => return Err(format!("unknown operator '{}'", op)),
+            };
+            return v.map(Value::Int).ok_or_else(|| "integer overflow".to_string());
+        }
+        let (a, b) = (l.as_float()?, r.as_float()?);
+        match op {
+            "+" => Ok(Value::Float(a + b)),
+            "-" => Ok(Value::Float(a - b)),
+            "*" => Ok(Value::Float(a * b)),
+            "/" => Ok(Value::Float(a / b)),
+            "%" => Ok(Value::Float(a % b)),
+            _ => Err(format!("unknown operator '{}'", op)),
+        }
+    }
+
+    ///
+    /// Apply the unary minus.
+    ///
+    pub fn negate(&self) -> Result<Value, String> {
+        match self {
+            Value::Int(i) => i.checked_neg().map(Value::Int).ok_or_else(|| "integer overflow".to_string()),
+            Value::Float(f) => Ok(Value::Float(-f)),
+            Value::Text(t) => Err(format!("'{}' is not a number", t)),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Text(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+///
+/// Read the value of a token.
+///
+/// Numeric literals are integers or floats,
+/// and others keep their text.
+///
+pub fn token_value(text: &str) -> Value {
+    if let Ok(i) = text.parse::<i64>() {
+        Value::Int(i)
+    } else if let Ok(f) = text.parse::<f64>() {
+        Value::Float(f)
+    } else {
+        Value::Text(text.to_string())
+    }
+}
+
+///
+/// A recursive descent evaluator
+/// for the action like `$1 + $3`.
+///
+/// ## Grammar
+/// ```text
+/// expr    -> term (('+' | '-') term)*
+/// term    -> unary (('*' | '/' | '%') unary)*
+/// unary   -> '-' unary | primary
+/// primary -> '$' digits | number | '(' expr ')'
+/// ```
+///
+struct ActionEval<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    args: &'a [Value],
+}
+
+impl<'a> ActionEval<'a> {
+    fn peek(&mut self) -> Option<char> {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expr(&mut self) -> Result<Value, String> {
+        let mut v = self.term()?;
+        while let Some(op) = self.peek().filter(|c| *c == '+' || *c == '-') {
+            self.pos += 1;
+            let r = self.term()?;
+            v = Value::binary(&op.to_string(), &v, &r)?;
+        }
+        Ok(v)
+    }
+
+    fn term(&mut self) -> Result<Value, String> {
+        let mut v = self.unary()?;
+        while let Some(op) = self.peek().filter(|c| *c == '*' || *c == '/' || *c == '%') {
+            self.pos += 1;
+            let r = self.unary()?;
+            v = Value::binary(&op.to_string(), &v, &r)?;
+        }
+        Ok(v)
+    }
+
+    fn unary(&mut self) -> Result<Value, String> {
+        if self.peek() == Some('-') {
+            self.pos += 1;
+            return self.unary()?.negate();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let v = self.expr()?;
+                if self.peek() != Some(')') {
+                    return Err("missing ')' in action".to_string());
+                }
+                self.pos += 1;
+                Ok(v)
+            }
+            Some('$') => {
+                self.pos += 1;
+                let n: usize = self.digits().parse().map_err(|_| "bad '$' in action".to_string())?;
+                match n.checked_sub(1).and_then(|i| self.args.get(i)) {
+                    Some(v) => Ok(v.clone()),
+                    None => Err(format!("${} is out of the production", n)),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let text = self.digits();
+                Ok(token_value(&text))
+            }
+            Some(c) => Err(format!("unexpected '{}' in action", c)),
+            None => Err("unexpected end of action".to_string()),
+        }
+    }
+
+    fn digits(&mut self) -> String {
+        let start = self.pos;
+        while self.pos < self.chars.len() && (self.chars[self.pos].is_ascii_digit() || self.chars[self.pos] == '.') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+///
+/// Execute an action on the values of the right side.
+///
+/// ## Example
+/// ```
+/// let v = eval_action("$1 + $3", &[Value::Int(1), Value::Text("+".to_string()), Value::Int(2)])?;
+/// ```
+///
+pub fn eval_action(action: &str, args: &[Value]) -> Result<Value, String> {
+    let mut eval = ActionEval {
+        chars: action.chars().collect(),
+        pos: 0,
+        args,
+    };
+    let v = eval.expr()?;
+    match eval.peek() {
+        None => Ok(v),
+        Some(c) => Err(format!("unexpected '{}' in action", c)),
+    }
+}
+
+///
+/// The built-in action for a production without braces.
+///
+/// ## Principles
+/// - `N op N` where `op` is arithmetic: apply `op`.
+/// - A single numeric value in the right side, like
+///   `F -> ( E )` or `F -> i`: pass it to the left side.
+///
+pub fn default_action(production: &Production, args: &[Value]) -> Result<Value, String> {
+    if let [l, Value::Text(op), r] = args {
+        if !matches!(l, Value::Text(_)) && !matches!(r, Value::Text(_)) {
+            return Value::binary(op, l, r);
+        }
+    }
+    let mut numbers = args.iter().filter(|v| !matches!(v, Value::Text(_)));
+    match (numbers.next(), numbers.next()) {
+        (Some(v), None) => Ok(v.clone()),
+        _ => Err(format!(
+            "no action for {} -> {}",
+            production.left,
+            production.right.join(" ")
+        )),
+    }
+}
+
+///
+/// A `Reducer` which evaluates the expression.
+///
+pub struct Evaluator;
+
+impl Reducer for Evaluator {
+    type Value = Value;
+
+    fn shift(&mut self, token: &Token) -> Result<Value, String> {
+        Ok(token_value(&token.text))
+    }
+
+    fn reduce(&mut self, production: &Production, children: Vec<Value>) -> Result<Value, String> {
+        match &production.action {
+            Some(action) => eval_action(action, &children),
+            None => default_action(production, &children),
+        }
+    }
+}
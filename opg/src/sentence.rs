@@ -0,0 +1,293 @@
+//! # sentence
+//!
+//! `sentence` generates the sentences and the
+//! sentential forms of a grammar, randomly or
+//! exhaustively up to a length.
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::Production;
+
+///
+/// The xorshift64* pseudo random number generator.
+///
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    ///
+    /// Create the generator from a seed,
+    /// where 0 is replaced since it stays 0.
+    ///
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            state: if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    ///
+    /// A number in `[0, n)`, `n` should be positive.
+    ///
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+///
+/// Generate the least height of the derivation
+/// tree of every productive non-terminal.
+///
+/// ## Principles
+/// The height of `A -> x` is 1 plus the largest height of
+/// the non-terminals in x, and the height of A is the least
+/// one of its productions. Repeat until nothing changes.
+///
+pub fn gen_heights(productions: &[Production], nts: &HashSet<String>) -> HashMap<String, usize> {
+    let mut heights: HashMap<String, usize> = HashMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for p in productions {
+            if let Some(h) = production_height(p, nts, &heights) {
+                if heights.get(&p.left).is_none_or(|old| h < *old) {
+                    heights.insert(p.left.clone(), h);
+                    changed = true;
+                }
+            }
+        }
+    }
+    heights
+}
+
+fn production_height(p: &Production, nts: &HashSet<String>, heights: &HashMap<String, usize>) -> Option<usize> {
+    let mut h = 0;
+    for v in p.right.iter().filter(|v| nts.contains(*v)) {
+        h = h.max(*heights.get(v)?);
+    }
+    Some(h + 1)
+}
+
+///
+/// The random generator over the productions.
+///
+pub struct Generator<'a> {
+    productions: &'a [Production],
+    nts: HashSet<String>,
+    heights: HashMap<String, usize>,
+    /// the weight of every production, 1 by default.
+    pub weights: Vec<u32>,
+    /// the bound of the height of the derivation tree.
+    pub depth: usize,
+}
+
+impl<'a> Generator<'a> {
+    ///
+    /// Create the generator for the productions.
+    ///
+    /// ## Return
+    /// An error if the starting non-terminal,
+    /// i.e. the left side of the first production,
+    /// derives no terminal string.
+    ///
+    pub fn new(productions: &'a [Production], nts: &HashSet<String>) -> Result<Generator<'a>, String> {
+        let heights = gen_heights(productions, nts);
+        let start = match productions.first() {
+            Some(p) => &p.left,
+            None => return Err("the grammar is empty".to_string()),
+        };
+        if !heights.contains_key(start) {
+            return Err(format!("'{}' derives no terminal string", start));
+        }
+        Ok(Generator {
+            productions,
+            nts: nts.clone(),
+            heights,
+            weights: vec![1; productions.len()],
+            depth: 10,
+        })
+    }
+
+    ///
+    /// Choose a production among `candidates` by the weights.
+    ///
+    fn choose(&self, rng: &mut Rng, candidates: &[usize]) -> Option<usize> {
+        let total: u64 = candidates.iter().map(|i| self.weights[*i] as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut r = rng.next_u64() % total;
+        for i in candidates {
+            let w = self.weights[*i] as u64;
+            if r < w {
+                return Some(*i);
+            }
+            r -= w;
+        }
+        None
+    }
+
+    ///
+    /// Generate a random sentence.
+    ///
+    /// ## Example
+    /// ```
+    /// let mut generator = sentence::Generator::new(&productions, &nts)?;
+    /// generator.depth = 8;
+    /// let s = generator.sentence(&mut sentence::Rng::new(42));
+    /// ```
+    ///
+    /// ## Principles
+    /// Expand every non-terminal at the depth d from the root
+    /// by a weighted choice among the productions whose least
+    /// height fits in the rest `depth - d`. If none fits, the
+    /// production with the least height is taken, so that
+    /// the generation always ends.
+    ///
+    pub fn sentence(&self, rng: &mut Rng) -> Vec<String> {
+        let mut output: Vec<String> = Vec::new();
+        self.expand(&self.productions[0].left, 0, rng, &mut output);
+        output
+    }
+
+    fn expand(&self, a: &str, d: usize, rng: &mut Rng, output: &mut Vec<String>) {
+        let mut least: Option<(usize, usize)> = None;
+        let mut candidates: Vec<usize> = Vec::new();
+        for (i, p) in self.productions.iter().enumerate() {
+            if p.left != a {
+                continue;
+            }
+            let h = match production_height(p, &self.nts, &self.heights) {
+                Some(h) => h,
+                None => continue,
+            };
+            if least.is_none_or(|(_, lh)| h < lh) {
+                least = Some((i, h));
+            }
+            if d + h <= self.depth {
+                candidates.push(i);
+            }
+        }
+        let i = match self.choose(rng, &candidates) {
+            Some(i) => i,
+            None => least.unwrap().0,
+        };
+        for v in self.productions[i].right.iter() {
+            if self.nts.contains(v) {
+                self.expand(v, d + 1, rng, output);
+            } else {
+                output.push(v.clone());
+            }
+        }
+    }
+
+    ///
+    /// Generate a random sentential form by
+    /// `steps` derivation steps at most from the start.
+    ///
+    /// ## Principles
+    /// Every step picks a non-terminal in the form
+    /// randomly, and replaces it with the right side of
+    /// a weighted choice of its productions.
+    ///
+    pub fn sentential_form(&self, steps: usize, rng: &mut Rng) -> Vec<String> {
+        let mut form: Vec<String> = vec![self.productions[0].left.clone()];
+        for _ in 0..steps {
+            let positions: Vec<usize> = (0..form.len()).filter(|k| self.nts.contains(&form[*k])).collect();
+            if positions.is_empty() {
+                break;
+            }
+            let k = positions[rng.below(positions.len())];
+            let candidates: Vec<usize> = (0..self.productions.len())
+                .filter(|i| self.productions[*i].left == form[k])
+                .collect();
+            let i = match self.choose(rng, &candidates) {
+                Some(i) => i,
+                None => break,
+            };
+            form.splice(k..k + 1, self.productions[i].right.iter().cloned());
+        }
+        form
+    }
+}
+
+///
+/// Enumerate all the sentences up to a length.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// `length` the largest number of tokens.
+///
+/// ## Return
+/// The sentences, shorter first, then in the
+/// lexicographical order.
+///
+/// ## Example
+/// ```
+/// for s in sentence::enumerate(&productions, &nts, 5) {
+///     println!("{}", s.join(" "));
+/// }
+/// ```
+///
+/// ## Principles
+/// Let L(A) be the strings of A up to the length. For every
+/// `A -> X1 X2 ... Xn`, add the concatenations of L(X1), ...,
+/// L(Xn) up to the length into L(A), where L(a) = {a} for a
+/// terminal. Repeat until nothing changes, which always ends
+/// as the strings are finite, even with ε or cycles.
+///
+pub fn enumerate(productions: &[Production], nts: &HashSet<String>, length: usize) -> Vec<Vec<String>> {
+    let mut langs: HashMap<String, BTreeSet<Vec<String>>> = HashMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for p in productions {
+            let mut strings: BTreeSet<Vec<String>> = BTreeSet::new();
+            strings.insert(Vec::new());
+            for v in p.right.iter() {
+                let mut next: BTreeSet<Vec<String>> = BTreeSet::new();
+                for s in strings.iter() {
+                    if !nts.contains(v) {
+                        if s.len() < length {
+                            let mut t = s.clone();
+                            t.push(v.clone());
+                            next.insert(t);
+                        }
+                        continue;
+                    }
+                    for u in langs.get(v).into_iter().flatten() {
+                        if s.len() + u.len() <= length {
+                            let mut t = s.clone();
+                            t.extend(u.iter().cloned());
+                            next.insert(t);
+                        }
+                    }
+                }
+                strings = next;
+            }
+            let lang = langs.entry(p.left.clone()).or_default();
+            for s in strings {
+                changed |= lang.insert(s);
+            }
+        }
+    }
+    let mut sentences: Vec<Vec<String>> = match productions.first() {
+        Some(p) => langs.remove(&p.left).unwrap_or_default().into_iter().collect(),
+        None => Vec::new(),
+    };
+    sentences.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+    sentences
+}
@@ -0,0 +1,252 @@
+//! # simple
+//!
+//! `simple` provides the simple precedence
+//! (Wirth-Weber) relations over all the grammar
+//! symbols and the simple precedence parser.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::dfs;
+use crate::lexer::Token;
+use crate::table;
+use crate::Production;
+
+///
+/// Generate HEAD+ set for
+/// every non-terminals.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// `nts` the non-terminal set, which could be generated
+/// from the function `get_non_terminals()`.
+///
+/// ## Return
+/// The symbols, both terminals and non-terminals,
+/// which could begin a string derived from the non-terminal.
+///
+/// ## Principles
+/// For every `U -> Xy`, X is in HEAD+(U), and
+/// HEAD+(X) is contained in HEAD+(U) if X is a non-terminal.
+/// Call `dfs::compose_elements()` to get the final result
+/// as FIRSTVT does.
+///
+pub fn gen_head(productions: &[Production], nts: &HashSet<String>) -> HashMap<String, HashSet<String>> {
+    gen_closure(productions, nts, |p| p.right.first())
+}
+
+///
+/// Generate TAIL+ set for
+/// every non-terminals.
+///
+/// ## Principles
+/// The same as `gen_head()` with the last symbol
+/// of the right side.
+///
+pub fn gen_tail(productions: &[Production], nts: &HashSet<String>) -> HashMap<String, HashSet<String>> {
+    gen_closure(productions, nts, |p| p.right.last())
+}
+
+fn gen_closure<F>(productions: &[Production], nts: &HashSet<String>, end: F) -> HashMap<String, HashSet<String>>
+where
+    F: Fn(&Production) -> Option<&String>,
+{
+    // every non-terminal needs an entry for the DFS
+    let mut mono: HashMap<String, HashSet<String>> =
+        nts.iter().map(|nt| (nt.clone(), HashSet::new())).collect();
+    let mut con: HashMap<String, HashSet<String>> = HashMap::new();
+    for p in productions {
+        if let Some(x) = end(p) {
+            mono.get_mut(&p.left).unwrap().insert(x.clone());
+            if nts.contains(x) {
+                con.entry(p.left.clone()).or_default().insert(x.clone());
+            }
+        }
+    }
+    dfs::compose_elements(&mono, &con)
+}
+
+///
+/// A struct of simple precedence matrix.
+///
+/// Every cell keeps all the relations,
+/// more than one relation means a conflict.
+///
+pub struct SpTable {
+    table: HashMap<(String, String), Vec<char>>,
+}
+
+impl SpTable {
+    fn insert(&mut self, a: &str, b: &str, ch: char) {
+        let cell = self.table.entry((a.to_string(), b.to_string())).or_default();
+        if !cell.contains(&ch) {
+            cell.push(ch);
+        }
+    }
+
+    ///
+    /// Build the matrix.
+    ///
+    /// ## Input
+    /// `productions` the vector of struct `Production`
+    ///
+    /// `nts` the non-terminal set.
+    ///
+    /// `head` and `tail` the sets from `gen_head()` and `gen_tail()`.
+    ///
+    /// ## Principles
+    /// For every adjacent `X Y` in a right side:
+    /// 1. X = Y;
+    /// 2. X < Z for every Z in HEAD+(Y);
+    /// 3. W > b for every W in TAIL+(X) and every terminal
+    ///    b which is Y or in HEAD+(Y).
+    ///
+    /// `$` is treated as `$ S $`, i.e. `$` < S and
+    /// HEAD+(S), while S and TAIL+(S) > `$`.
+    ///
+    pub fn new(
+        productions: &[Production],
+        nts: &HashSet<String>,
+        head: &HashMap<String, HashSet<String>>,
+        tail: &HashMap<String, HashSet<String>>,
+    ) -> SpTable {
+        let mut table = SpTable {
+            table: HashMap::new(),
+        };
+        let empty: HashSet<String> = HashSet::new();
+        for p in productions {
+            for pair in p.right.windows(2) {
+                let (x, y) = (&pair[0], &pair[1]);
+                table.insert(x, y, '=');
+                let heads = head.get(y).unwrap_or(&empty);
+                for z in heads {
+                    table.insert(x, z, '<');
+                }
+                if let Some(tails) = tail.get(x) {
+                    let bs = std::iter::once(y).chain(heads.iter()).filter(|b| !nts.contains(*b));
+                    for b in bs {
+                        for w in tails {
+                            table.insert(w, b, '>');
+                        }
+                    }
+                }
+            }
+        }
+        // the boundary $ S $
+        let start = &productions[0].left;
+        table.insert("$", start, '<');
+        for z in head.get(start).unwrap_or(&empty) {
+            table.insert("$", z, '<');
+        }
+        table.insert(start, "$", '>');
+        for w in tail.get(start).unwrap_or(&empty) {
+            table.insert(w, "$", '>');
+        }
+        table
+    }
+
+    ///
+    /// The relations between `a` on the left
+    /// and `b` on the right.
+    ///
+    pub fn get(&self, a: &str, b: &str) -> &[char] {
+        self.table
+            .get(&(a.to_string(), b.to_string()))
+            .map_or(&[], |v| v.as_slice())
+    }
+
+    ///
+    /// Report the cells with more than one relation.
+    ///
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut keys: Vec<&(String, String)> =
+            self.table.keys().filter(|k| self.table[*k].len() > 1).collect();
+        keys.sort();
+        keys.iter()
+            .map(|k| {
+                let rels: Vec<String> = self.table[*k].iter().map(|c| format!("{} {} {}", k.0, c, k.1)).collect();
+                format!("Precedence conflict: {}", rels.join(", "))
+            })
+            .collect()
+    }
+
+    ///
+    /// Render the matrix in the format of `OpTable`.
+    ///
+    pub fn render(&self, symbols: &[String]) -> String {
+        table::render(symbols, symbols, |i, j| {
+            let cell = self.get(i, j);
+            if cell.is_empty() {
+                " ".to_string()
+            } else {
+                cell.iter().map(|c| c.to_string()).collect::<Vec<_>>().join("/")
+            }
+        })
+    }
+}
+
+///
+/// Parse the tokens with the simple precedence matrix.
+///
+/// ## Input
+/// `table` the `SpTable` of the grammar.
+///
+/// `productions` the vector of struct `Production`
+///
+/// `tokens` the input ended with `$`.
+///
+/// ## Return
+/// The steps of the parsing as `(stack, input, action)`.
+///
+/// ## Principles
+/// Compare the top of the stack with the input:
+/// - `<` or `=`: shift the input.
+/// - `>`: go down the stack until a `<` is met, the symbols
+///   above it form the handle, which must be exactly the right
+///   side of a production. Replace it with the left side.
+///
+/// Accept if the stack is `$ S` and the input is `$`.
+///
+pub fn parse(table: &SpTable, productions: &[Production], tokens: &[Token]) -> Result<Vec<(String, String, String)>, String> {
+    let start = &productions[0].left;
+    let mut stack: Vec<String> = vec!["$".to_string()];
+    let mut steps: Vec<(String, String, String)> = Vec::new();
+    let mut ip = 0;
+    loop {
+        let a = tokens.get(ip).map_or("$", |t| t.kind.as_str());
+        let input: Vec<&str> = tokens[ip.min(tokens.len())..].iter().map(|t| t.kind.as_str()).collect();
+        let record = |stack: &Vec<String>, action: String| (stack.join(" "), input.join(" "), action);
+        if a == "$" && stack.len() == 2 && stack[1] == *start {
+            steps.push(record(&stack, "accept".to_string()));
+            return Ok(steps);
+        }
+        let top = stack.last().unwrap().clone();
+        match table.get(&top, a) {
+            ['<'] | ['='] => {
+                steps.push(record(&stack, format!("shift {}", a)));
+                stack.push(a.to_string());
+                ip += 1;
+            }
+            ['>'] => {
+                let mut i = stack.len() - 1;
+                while i > 0 && table.get(&stack[i - 1], &stack[i]) != ['<'] {
+                    i -= 1;
+                }
+                if i == 0 {
+                    return Err(format!("no handle before '{}'", a));
+                }
+                let handle = &stack[i..];
+                let p = match productions.iter().find(|p| p.right == handle) {
+                    Some(p) => p,
+                    None => return Err(format!("no production for '{}'", handle.join(" "))),
+                };
+                steps.push(record(&stack, format!("reduce {}", p)));
+                stack.truncate(i);
+                stack.push(p.left.clone());
+            }
+            [] => return Err(format!("no relation between '{}' and '{}'", top, a)),
+            _ => return Err(format!("conflict between '{}' and '{}'", top, a)),
+        }
+    }
+}
@@ -8,6 +8,8 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::{self, Display, Formatter};
 
+use crate::compress::{self, Compressed};
+
 ///
 /// A struct of Operation Table.
 ///
@@ -15,7 +17,9 @@ pub struct OpTable{
     /// The hashmap of relation.
     table: HashMap<(String, String), char>,
     /// The terminal set in the grammar.
-    ts: HashSet<String>
+    ts: HashSet<String>,
+    /// The conflicting relations met by `insert()`.
+    conflicts: Vec<(String, String, char, char)>,
 }
 
 impl OpTable {
@@ -25,7 +29,8 @@ impl OpTable {
     pub fn new(ts: HashSet<String>) -> OpTable {
         OpTable {
             table: HashMap::new(),
-            ts: ts
+            ts,
+            conflicts: Vec::new(),
         }
     }
 
@@ -35,43 +40,203 @@ impl OpTable {
     /// ## Principles
     /// Try to insert the a tuple with `ch` relation.
     /// If it is occupied and not equal to the relation
-    /// to be inserted, then the grammar is ambiguous
-    /// and the panic will be fired.
+    /// to be inserted, then the grammar is ambiguous,
+    /// the conflict is recorded and the first relation is kept.
     ///
     pub fn insert(&mut self, ttuple: &(String, String), ch: char) {
-        if self.table.contains_key(&ttuple) && self.table[&ttuple] != ch {
-            println!("The grammar is ambiguous.");
-            panic!("Ambiguous grammar detected.");
+        match self.table.get(ttuple) {
+            Some(old) if *old != ch => {
+                let conflict = (ttuple.0.clone(), ttuple.1.clone(), *old, ch);
+                if !self.conflicts.contains(&conflict) {
+                    self.conflicts.push(conflict);
+                }
+            }
+            Some(_) => {}
+            None => {
+                self.table.insert(ttuple.clone(), ch);
+            }
         }
-        self.table.insert(ttuple.clone(), ch);
     }
 
     ///
-    /// Convert table to string.
+    /// The conflicts as `(a, b, kept, rejected)`.
+    ///
+    /// The grammar is not an operator precedence
+    /// grammar if there is any.
+    ///
+    pub fn conflicts(&self) -> &[(String, String, char, char)] {
+        &self.conflicts
+    }
+
+    ///
+    /// Query the relation between
+    /// terminal `a` on the left and
+    /// terminal `b` on the right.
+    ///
+    /// ## Return
+    /// One of `<`, `=`, `>` or `None`
+    /// if the pair has no relation.
+    ///
+    pub fn get(&self, a: &str, b: &str) -> Option<char> {
+        self.table.get(&(a.to_string(), b.to_string())).copied()
+    }
+
+    ///
+    /// The terminal set of the table.
+    ///
+    pub fn terminals(&self) -> &HashSet<String> {
+        &self.ts
+    }
+
+    ///
+    /// Generate the precedence functions f and g.
     ///
-    pub fn to_string(&self) -> String{
-        let mut output:String = "".to_owned();
-        output = output + " \t";
-        for j in self.ts.iter() {
-            output  = output + j + "\t";
+    /// ## Input
+    /// `order` the terminals in order.
+    ///
+    /// ## Return
+    /// `f` and `g` in the order, such that `f(a) < g(b)`,
+    /// `f(a) = g(b)` or `f(a) > g(b)` for every relation,
+    /// or `None` if they do not exist.
+    ///
+    /// ## Example
+    /// ```
+    /// if let Some((f, g)) = table.gen_functions(&tv) {
+    ///     println!("{:?} {:?}", f, g);
+    /// }
+    /// ```
+    ///
+    /// ## Principles
+    /// 1. Make a node for every f(a) and g(a), and merge
+    ///    f(a) and g(b) into one node if `a = b`.
+    /// 2. Draw an edge from f(a) to g(b) if `a > b`,
+    ///    and from g(b) to f(a) if `a < b`.
+    /// 3. The function of a node is the length of the
+    ///    longest path from it. They do not exist if
+    ///    there is a cycle.
+    ///
+    pub fn gen_functions(&self, order: &[String]) -> Option<(Vec<usize>, Vec<usize>)> {
+        let n = order.len();
+        // f(a) is node i, and g(a) is node n + i
+        let mut parent: Vec<usize> = (0..2 * n).collect();
+        fn find(parent: &mut [usize], x: usize) -> usize {
+            let mut root = x;
+            while parent[root] != root {
+                root = parent[root];
+            }
+            parent[x] = root;
+            root
+        }
+        for (i, a) in order.iter().enumerate() {
+            for (j, b) in order.iter().enumerate() {
+                if self.get(a, b) == Some('=') {
+                    let (x, y) = (find(&mut parent, i), find(&mut parent, n + j));
+                    parent[x] = y;
+                }
+            }
         }
-        output  = output + "\n";
-
-        for i in self.ts.iter() {
-            output  = output + i + "\t";
-            for j in self.ts.iter() {
-                let ttuple = (i.clone(), j.clone());
-                if self.table.contains_key(&ttuple) {
-                    output = output + &self.table[&ttuple].to_string() + "\t";
-                } else {
-                    output = output + " \t";
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); 2 * n];
+        for (i, a) in order.iter().enumerate() {
+            for (j, b) in order.iter().enumerate() {
+                let (fa, gb) = (find(&mut parent, i), find(&mut parent, n + j));
+                match self.get(a, b) {
+                    Some('>') => edges[fa].push(gb),
+                    Some('<') => edges[gb].push(fa),
+                    _ => {}
                 }
             }
-            output = output + "\n";
         }
-        output
+        // the longest paths by the depth first search,
+        // where `None` is unvisited and `Some(None)` is on the path
+        let mut length: Vec<Option<Option<usize>>> = vec![None; 2 * n];
+        fn longest(x: usize, edges: &[Vec<usize>], length: &mut [Option<Option<usize>>]) -> Option<usize> {
+            match length[x] {
+                Some(Some(l)) => return Some(l),
+                Some(None) => return None,
+                None => {}
+            }
+            length[x] = Some(None);
+            let mut l = 0;
+            for y in edges[x].iter() {
+                l = l.max(longest(*y, edges, length)? + 1);
+            }
+            length[x] = Some(Some(l));
+            Some(l)
+        }
+        let mut f: Vec<usize> = Vec::new();
+        let mut g: Vec<usize> = Vec::new();
+        for i in 0..n {
+            let (fa, ga) = (find(&mut parent, i), find(&mut parent, n + i));
+            f.push(longest(fa, &edges, &mut length)?);
+            g.push(longest(ga, &edges, &mut length)?);
+        }
+        Some((f, g))
+    }
+
+    ///
+    /// Compress the table.
+    ///
+    /// ## Input
+    /// `order` the terminals in order.
+    ///
+    /// ## Return
+    /// The precedence functions if they exist, or else the
+    /// smaller one of the tables by `compress::merge()` and
+    /// `compress::pack()`.
+    ///
+    /// ## Example
+    /// ```
+    /// match table.compress(&tv) {
+    ///     Compressed::Functions(f, g) => println!("{:?} {:?}", f, g),
+    ///     Compressed::Merged(merged) => println!("{} x {}", merged.height(), merged.width()),
+    ///     Compressed::Packed(packed) => println!("{} slots", packed.slots.len()),
+    /// }
+    /// ```
+    ///
+    /// ## Principles
+    /// The functions are the smallest, but they have no blank,
+    /// so an error is found later by the parser. The merging
+    /// suits the tables with many terminals of the same
+    /// precedence, and the comb vector suits the sparse ones.
+    ///
+    pub fn compress(&self, order: &[String]) -> Compressed {
+        if let Some((f, g)) = self.gen_functions(order) {
+            return Compressed::Functions(f, g);
+        }
+        let (merged, packed) = (compress::merge(self, order), compress::pack(self, order));
+        if packed.size() < merged.size() {
+            Compressed::Packed(packed)
+        } else {
+            Compressed::Merged(merged)
+        }
+    }
+}
+
+///
+/// The queries on an operator precedence table, shared
+/// by `OpTable` and `binary::BinaryTable` for `parser::parse()`.
+///
+pub trait Relations {
+    ///
+    /// Query the relation between terminal `a`
+    /// on the left and terminal `b` on the right.
+    ///
+    fn get(&self, a: &str, b: &str) -> Option<char>;
+
+    ///
+    /// Whether `t` is a terminal of the table.
+    ///
+    fn contains(&self, t: &str) -> bool;
+}
+
+impl Relations for OpTable {
+    fn get(&self, a: &str, b: &str) -> Option<char> {
+        OpTable::get(self, a, b)
     }
 
+    fn contains(&self, t: &str) -> bool {
+        self.ts.contains(t)
+    }
 }
 
 impl Display for OpTable {
@@ -80,7 +245,61 @@ impl Display for OpTable {
     /// an `opTable` struct.
     ///
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        let output = self.to_string();
+        let ts: Vec<String> = self.ts.iter().cloned().collect();
+        let output = render(&ts, &ts, |i, j| match self.get(i, j) {
+            Some(ch) => ch.to_string(),
+            None => " ".to_string(),
+        });
         write!(f, "{}", output)
-    }    
+    }
+}
+
+///
+/// Output a conflict from `OpTable::conflicts()` like
+/// `precedence conflict between '+' and '+': '<' and '>'`.
+///
+pub fn format_conflict(conflict: &(String, String, char, char)) -> String {
+    let (a, b, kept, rejected) = conflict;
+    format!(
+        "precedence conflict between '{}' and '{}': '{}' and '{}'",
+        a, b, kept, rejected
+    )
+}
+
+///
+/// Render a matrix in the format of `OpTable`.
+///
+/// ## Input
+/// `rows` the symbols on the left.
+///
+/// `cols` the symbols on the top.
+///
+/// `cell` the content of the cell in row `i` and column `j`.
+///
+/// ## Example
+/// ```
+/// let output = table::render(&nts, &ts, |i, j| cell(i, j));
+/// ```
+///
+/// ## Principles
+/// Every cell is followed by a tab,
+/// and every row is followed by a line break.
+///
+pub fn render<F>(rows: &[String], cols: &[String], cell: F) -> String
+where
+    F: Fn(&str, &str) -> String,
+{
+    let mut output = String::from(" \t");
+    for j in cols.iter() {
+        output = output + j + "\t";
+    }
+    output += "\n";
+    for i in rows.iter() {
+        output = output + i + "\t";
+        for j in cols.iter() {
+            output = output + &cell(i, j) + "\t";
+        }
+        output += "\n";
+    }
+    output
 }
@@ -0,0 +1,356 @@
+//! # transform
+//!
+//! `transform` rewrites a grammar into
+//! an equivalent one, keeping the mapping
+//! from the new non-terminals to the originals.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+use crate::ll1;
+use crate::{fresh_symbol, Production};
+
+///
+/// The productions grouped by the left side,
+/// in the order of appearance.
+///
+type Rules = Vec<(String, Vec<Vec<String>>)>;
+
+fn group(productions: &[Production]) -> Rules {
+    let mut rules: Rules = Vec::new();
+    for p in productions {
+        match rules.iter_mut().find(|(left, _)| *left == p.left) {
+            Some((_, alts)) => alts.push(p.right.clone()),
+            None => rules.push((p.left.clone(), vec![p.right.clone()])),
+        }
+    }
+    rules
+}
+
+fn ungroup(rules: &Rules) -> Vec<Production> {
+    let mut productions: Vec<Production> = Vec::new();
+    for (left, alts) in rules {
+        for right in alts {
+            if !productions.iter().any(|p| p.left == *left && p.right == *right) {
+                productions.push(Production::new(left, right.clone()));
+            }
+        }
+    }
+    productions
+}
+
+fn symbols(productions: &[Production]) -> HashSet<String> {
+    let mut used: HashSet<String> = HashSet::new();
+    for p in productions {
+        used.insert(p.left.clone());
+        used.extend(p.right.iter().cloned());
+    }
+    used
+}
+
+///
+/// Format the mapping as `%origin` directives,
+/// which are skipped by `gen_productions()`.
+///
+pub fn format_origins(origins: &[(String, String)]) -> String {
+    let mut output = String::new();
+    for (new, old) in origins {
+        output += &format!("%origin {} {}\n", new, old);
+    }
+    output
+}
+
+///
+/// Eliminate the direct and indirect left recursion.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Return
+/// The new productions, and the mapping from every
+/// new non-terminal to the original one, like `E' -> E`.
+///
+/// ## Example
+/// ```
+/// let (productions, origins) = transform::eliminate_left_recursion(&productions);
+/// ```
+///
+/// ## Principles
+/// Order the non-terminals as A1, A2, ..., An. For every Ai:
+/// 1. For every j < i, replace `Ai -> Aj y` with `Ai -> x y`
+///    for every `Aj -> x`, which removes the indirect recursion.
+/// 2. Replace `Ai -> Ai a | b` with `Ai -> b Ai'` and
+///    `Ai' -> a Ai' | ε`, which removes the direct recursion.
+///
+/// `A -> A` is dropped since it adds nothing but a cycle.
+///
+pub fn eliminate_left_recursion(productions: &[Production]) -> (Vec<Production>, Vec<(String, String)>) {
+    let mut used = symbols(productions);
+    let mut rules = group(productions);
+    let originals: Vec<String> = rules.iter().map(|(left, _)| left.clone()).collect();
+    let mut origins: Vec<(String, String)> = Vec::new();
+    for (i, ai) in originals.iter().enumerate() {
+        let pos = rules.iter().position(|(left, _)| left == ai).unwrap();
+        // the indirect recursion
+        for aj in originals[..i].iter() {
+            let ajalts = rules.iter().find(|(left, _)| left == aj).unwrap().1.clone();
+            let mut alts: Vec<Vec<String>> = Vec::new();
+            for right in rules[pos].1.iter() {
+                if right.first() == Some(aj) {
+                    for x in ajalts.iter() {
+                        let mut new = x.clone();
+                        new.extend(right[1..].iter().cloned());
+                        alts.push(new);
+                    }
+                } else {
+                    alts.push(right.clone());
+                }
+            }
+            rules[pos].1 = alts;
+        }
+        // the direct recursion
+        let (recursive, others): (Vec<Vec<String>>, Vec<Vec<String>>) =
+            rules[pos].1.iter().cloned().partition(|right| right.first() == Some(ai));
+        if recursive.is_empty() {
+            continue;
+        }
+        let new = fresh_symbol(&format!("{}'", ai), &used);
+        used.insert(new.clone());
+        origins.push((new.clone(), ai.clone()));
+        rules[pos].1 = others
+            .into_iter()
+            .map(|mut beta| {
+                beta.push(new.clone());
+                beta
+            })
+            .collect();
+        let mut newalts: Vec<Vec<String>> = recursive
+            .into_iter()
+            .filter(|right| right.len() > 1)
+            .map(|right| {
+                let mut alpha = right[1..].to_vec();
+                alpha.push(new.clone());
+                alpha
+            })
+            .collect();
+        newalts.push(Vec::new());
+        rules.insert(pos + 1, (new, newalts));
+    }
+    (ungroup(&rules), origins)
+}
+
+///
+/// Left-factor the alternatives with common prefixes.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Return
+/// The new productions, and the mapping from every
+/// new non-terminal to the original one.
+///
+/// ## Example
+/// ```
+/// let (productions, origins) = transform::left_factor(&productions);
+/// ```
+///
+/// ## Principles
+/// For the alternatives of A beginning with the same symbol,
+/// take their longest common prefix x, and replace
+/// `A -> x y1 | x y2 | ...` with `A -> x A'` and
+/// `A' -> y1 | y2 | ...`. Repeat on all the non-terminals,
+/// including the new ones, until nothing changes.
+///
+pub fn left_factor(productions: &[Production]) -> (Vec<Production>, Vec<(String, String)>) {
+    let mut used = symbols(productions);
+    let mut rules = group(productions);
+    let mut origins: Vec<(String, String)> = Vec::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut pos = 0;
+        while pos < rules.len() {
+            let (left, alts) = rules[pos].clone();
+            let mut alts = alts;
+            alts.dedup();
+            // the first group of alternatives sharing the first symbol
+            let group: Option<Vec<usize>> = alts.iter().enumerate().find_map(|(i, right)| {
+                let first = right.first()?;
+                let same: Vec<usize> = (i..alts.len()).filter(|j| alts[*j].first() == Some(first)).collect();
+                if same.len() > 1 {
+                    Some(same)
+                } else {
+                    None
+                }
+            });
+            let same = match group {
+                Some(same) => same,
+                None => {
+                    pos += 1;
+                    continue;
+                }
+            };
+            // the longest common prefix
+            let mut len = 1;
+            while same.iter().all(|j| alts[*j].len() > len && alts[*j][len] == alts[same[0]][len]) {
+                len += 1;
+            }
+            let prefix: Vec<String> = alts[same[0]][..len].to_vec();
+            let new = fresh_symbol(&format!("{}'", left), &used);
+            used.insert(new.clone());
+            let old = match origins.iter().find(|(n, _)| *n == left) {
+                Some((_, o)) => o.clone(),
+                None => left.clone(),
+            };
+            origins.push((new.clone(), old));
+            let suffixes: Vec<Vec<String>> = same.iter().map(|j| alts[*j][len..].to_vec()).collect();
+            let mut factored = prefix;
+            factored.push(new.clone());
+            let mut newalts: Vec<Vec<String>> = Vec::new();
+            for (i, right) in alts.into_iter().enumerate() {
+                if i == same[0] {
+                    newalts.push(factored.clone());
+                } else if !same.contains(&i) {
+                    newalts.push(right);
+                }
+            }
+            rules[pos].1 = newalts;
+            rules.insert(pos + 1, (new, suffixes));
+            changed = true;
+        }
+    }
+    (ungroup(&rules), origins)
+}
+
+///
+/// Eliminate the unit productions like `A -> B`.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Return
+/// The new productions, and the mapping, which is
+/// always empty since no non-terminal is introduced.
+///
+/// ## Example
+/// ```
+/// let (productions, _) = transform::eliminate_unit(&productions);
+/// ```
+///
+/// ## Principles
+/// For every A, find the non-terminals B with `A =>* B` by
+/// the unit productions only, and give A every non-unit
+/// production `B -> x` as `A -> x`. The cycles made of
+/// the unit productions vanish with them.
+///
+pub fn eliminate_unit(productions: &[Production]) -> (Vec<Production>, Vec<(String, String)>) {
+    let rules = group(productions);
+    let is_unit = |right: &Vec<String>| right.len() == 1 && rules.iter().any(|(left, _)| *left == right[0]);
+    let mut newrules: Rules = Vec::new();
+    for (a, _) in rules.iter() {
+        // the non-terminals reached by the unit productions
+        let mut reach: Vec<&String> = vec![a];
+        let mut i = 0;
+        while i < reach.len() {
+            let alts = &rules.iter().find(|(left, _)| left == reach[i]).unwrap().1;
+            for right in alts.iter().filter(|right| is_unit(right)) {
+                if !reach.contains(&&right[0]) {
+                    reach.push(&right[0]);
+                }
+            }
+            i += 1;
+        }
+        let mut alts: Vec<Vec<String>> = Vec::new();
+        for b in reach {
+            let balts = &rules.iter().find(|(left, _)| left == b).unwrap().1;
+            alts.extend(balts.iter().filter(|right| !is_unit(right)).cloned());
+        }
+        newrules.push((a.clone(), alts));
+    }
+    (ungroup(&newrules), Vec::new())
+}
+
+///
+/// Find the derivation cycles `A =>+ A`.
+///
+/// ## Input
+/// `productions` the vector of struct `Production`
+///
+/// ## Return
+/// The indices of the productions in every cycle,
+/// in the order of the derivation. A non-terminal
+/// shows up in one cycle at most.
+///
+/// ## Example
+/// ```
+/// for cycle in transform::find_cycles(&productions) {
+///     println!("{}", transform::format_cycle(&productions, &cycle));
+/// }
+/// ```
+///
+/// ## Principles
+/// `A -> x B y` is an edge from A to B if x and y
+/// are nullable, so that `A =>+ B`. For every A, search
+/// the shortest path back to A by breadth first.
+///
+pub fn find_cycles(productions: &[Production]) -> Vec<Vec<usize>> {
+    let nts: HashSet<String> = productions.iter().map(|p| p.left.clone()).collect();
+    let nullable = ll1::gen_nullable(productions);
+    let mut edges: Vec<(&String, &String, usize)> = Vec::new();
+    for (i, p) in productions.iter().enumerate() {
+        for (k, b) in p.right.iter().enumerate() {
+            let others = p.right.iter().enumerate().filter(|(j, _)| *j != k);
+            if nts.contains(b) && others.into_iter().all(|(_, v)| nullable.contains(v)) {
+                edges.push((&p.left, b, i));
+            }
+        }
+    }
+    let mut cycles: Vec<Vec<usize>> = Vec::new();
+    let mut covered: HashSet<&String> = HashSet::new();
+    for p in productions {
+        let a = &p.left;
+        if covered.contains(a) {
+            continue;
+        }
+        // the edge reaching every node first
+        let mut parent: HashMap<&String, (&String, usize)> = HashMap::new();
+        let mut queue: VecDeque<&String> = VecDeque::from(vec![a]);
+        let mut found: Option<(&String, usize)> = None;
+        'search: while let Some(x) = queue.pop_front() {
+            for (from, to, i) in edges.iter().filter(|(from, _, _)| *from == x) {
+                if *to == a {
+                    found = Some((from, *i));
+                    break 'search;
+                }
+                if !parent.contains_key(to) && !covered.contains(to) {
+                    parent.insert(to, (from, *i));
+                    queue.push_back(to);
+                }
+            }
+        }
+        if let Some((mut x, i)) = found {
+            let mut cycle: Vec<usize> = vec![i];
+            covered.insert(a);
+            while x != a {
+                covered.insert(x);
+                let (from, i) = parent[x];
+                cycle.push(i);
+                x = from;
+            }
+            cycle.reverse();
+            cycles.push(cycle);
+        }
+    }
+    cycles
+}
+
+///
+/// Output a cycle like `A => B => A: A -> B, B -> A`.
+///
+pub fn format_cycle(productions: &[Production], cycle: &[usize]) -> String {
+    let mut chain: Vec<&str> = cycle.iter().map(|i| productions[*i].left.as_str()).collect();
+    chain.push(&productions[cycle[0]].left);
+    let ps: Vec<String> = cycle.iter().map(|i| productions[*i].to_string()).collect();
+    format!("{}: {}", chain.join(" => "), ps.join(", "))
+}